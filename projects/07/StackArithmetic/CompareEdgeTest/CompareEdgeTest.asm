@@ -0,0 +1,1054 @@
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 0
+@0
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// gt
+@SP
+M=M-1
+A=M
+D=M
+@R13
+M=D
+@SP
+M=M-1
+A=M
+D=M
+@R14
+M=D
+@_COMP_LABEL_0_X_NEG
+D; JLT
+@R13
+D=M
+@_COMP_LABEL_0_TRUE
+D; JLT
+@_COMP_LABEL_0_SUB
+0; JMP
+(_COMP_LABEL_0_X_NEG)
+@R13
+D=M
+@_COMP_LABEL_0_FALSE
+D; JGE
+(_COMP_LABEL_0_SUB)
+@R13
+D=M
+@R14
+D=M-D
+@_COMP_LABEL_0_TRUE
+D; JGT
+@_COMP_LABEL_0_FALSE
+0; JMP
+(_COMP_LABEL_0_TRUE)
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+@_COMP_LABEL_0_END
+0; JMP
+(_COMP_LABEL_0_FALSE)
+@SP
+A=M
+M=0
+@SP
+M=M+1
+(_COMP_LABEL_0_END)
+
+// push constant 0
+@0
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// lt
+@SP
+M=M-1
+A=M
+D=M
+@R13
+M=D
+@SP
+M=M-1
+A=M
+D=M
+@R14
+M=D
+@_COMP_LABEL_1_X_NEG
+D; JLT
+@R13
+D=M
+@_COMP_LABEL_1_FALSE
+D; JLT
+@_COMP_LABEL_1_SUB
+0; JMP
+(_COMP_LABEL_1_X_NEG)
+@R13
+D=M
+@_COMP_LABEL_1_TRUE
+D; JGE
+(_COMP_LABEL_1_SUB)
+@R13
+D=M
+@R14
+D=M-D
+@_COMP_LABEL_1_TRUE
+D; JLT
+@_COMP_LABEL_1_FALSE
+0; JMP
+(_COMP_LABEL_1_TRUE)
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+@_COMP_LABEL_1_END
+0; JMP
+(_COMP_LABEL_1_FALSE)
+@SP
+A=M
+M=0
+@SP
+M=M+1
+(_COMP_LABEL_1_END)
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 1
+@1
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// lt
+@SP
+M=M-1
+A=M
+D=M
+@R13
+M=D
+@SP
+M=M-1
+A=M
+D=M
+@R14
+M=D
+@_COMP_LABEL_2_X_NEG
+D; JLT
+@R13
+D=M
+@_COMP_LABEL_2_FALSE
+D; JLT
+@_COMP_LABEL_2_SUB
+0; JMP
+(_COMP_LABEL_2_X_NEG)
+@R13
+D=M
+@_COMP_LABEL_2_TRUE
+D; JGE
+(_COMP_LABEL_2_SUB)
+@R13
+D=M
+@R14
+D=M-D
+@_COMP_LABEL_2_TRUE
+D; JLT
+@_COMP_LABEL_2_FALSE
+0; JMP
+(_COMP_LABEL_2_TRUE)
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+@_COMP_LABEL_2_END
+0; JMP
+(_COMP_LABEL_2_FALSE)
+@SP
+A=M
+M=0
+@SP
+M=M+1
+(_COMP_LABEL_2_END)
+
+// push constant 1
+@1
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// gt
+@SP
+M=M-1
+A=M
+D=M
+@R13
+M=D
+@SP
+M=M-1
+A=M
+D=M
+@R14
+M=D
+@_COMP_LABEL_3_X_NEG
+D; JLT
+@R13
+D=M
+@_COMP_LABEL_3_TRUE
+D; JLT
+@_COMP_LABEL_3_SUB
+0; JMP
+(_COMP_LABEL_3_X_NEG)
+@R13
+D=M
+@_COMP_LABEL_3_FALSE
+D; JGE
+(_COMP_LABEL_3_SUB)
+@R13
+D=M
+@R14
+D=M-D
+@_COMP_LABEL_3_TRUE
+D; JGT
+@_COMP_LABEL_3_FALSE
+0; JMP
+(_COMP_LABEL_3_TRUE)
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+@_COMP_LABEL_3_END
+0; JMP
+(_COMP_LABEL_3_FALSE)
+@SP
+A=M
+M=0
+@SP
+M=M+1
+(_COMP_LABEL_3_END)
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// gt
+@SP
+M=M-1
+A=M
+D=M
+@R13
+M=D
+@SP
+M=M-1
+A=M
+D=M
+@R14
+M=D
+@_COMP_LABEL_4_X_NEG
+D; JLT
+@R13
+D=M
+@_COMP_LABEL_4_TRUE
+D; JLT
+@_COMP_LABEL_4_SUB
+0; JMP
+(_COMP_LABEL_4_X_NEG)
+@R13
+D=M
+@_COMP_LABEL_4_FALSE
+D; JGE
+(_COMP_LABEL_4_SUB)
+@R13
+D=M
+@R14
+D=M-D
+@_COMP_LABEL_4_TRUE
+D; JGT
+@_COMP_LABEL_4_FALSE
+0; JMP
+(_COMP_LABEL_4_TRUE)
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+@_COMP_LABEL_4_END
+0; JMP
+(_COMP_LABEL_4_FALSE)
+@SP
+A=M
+M=0
+@SP
+M=M+1
+(_COMP_LABEL_4_END)
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// lt
+@SP
+M=M-1
+A=M
+D=M
+@R13
+M=D
+@SP
+M=M-1
+A=M
+D=M
+@R14
+M=D
+@_COMP_LABEL_5_X_NEG
+D; JLT
+@R13
+D=M
+@_COMP_LABEL_5_FALSE
+D; JLT
+@_COMP_LABEL_5_SUB
+0; JMP
+(_COMP_LABEL_5_X_NEG)
+@R13
+D=M
+@_COMP_LABEL_5_TRUE
+D; JGE
+(_COMP_LABEL_5_SUB)
+@R13
+D=M
+@R14
+D=M-D
+@_COMP_LABEL_5_TRUE
+D; JLT
+@_COMP_LABEL_5_FALSE
+0; JMP
+(_COMP_LABEL_5_TRUE)
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+@_COMP_LABEL_5_END
+0; JMP
+(_COMP_LABEL_5_FALSE)
+@SP
+A=M
+M=0
+@SP
+M=M+1
+(_COMP_LABEL_5_END)
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// eq
+@SP
+M=M-1
+A=M
+D=M
+@SP
+M=M-1
+A=M
+A=M
+D=A-D
+@_COMP_LABEL_6_TRUE
+D; JEQ
+@_COMP_LABEL_6_FALSE
+0; JMP
+(_COMP_LABEL_6_TRUE)
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+@_COMP_LABEL_6_END
+0; JMP
+(_COMP_LABEL_6_FALSE)
+@SP
+A=M
+M=0
+@SP
+M=M+1
+(_COMP_LABEL_6_END)
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 0
+@0
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// eq
+@SP
+M=M-1
+A=M
+D=M
+@SP
+M=M-1
+A=M
+A=M
+D=A-D
+@_COMP_LABEL_7_TRUE
+D; JEQ
+@_COMP_LABEL_7_FALSE
+0; JMP
+(_COMP_LABEL_7_TRUE)
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+@_COMP_LABEL_7_END
+0; JMP
+(_COMP_LABEL_7_FALSE)
+@SP
+A=M
+M=0
+@SP
+M=M+1
+(_COMP_LABEL_7_END)
+
+// push constant 0
+@0
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 2
+@2
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// neg
+@SP
+M=M-1
+A=M
+D=M
+D=-D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// gt
+@SP
+M=M-1
+A=M
+D=M
+@R13
+M=D
+@SP
+M=M-1
+A=M
+D=M
+@R14
+M=D
+@_COMP_LABEL_8_X_NEG
+D; JLT
+@R13
+D=M
+@_COMP_LABEL_8_TRUE
+D; JLT
+@_COMP_LABEL_8_SUB
+0; JMP
+(_COMP_LABEL_8_X_NEG)
+@R13
+D=M
+@_COMP_LABEL_8_FALSE
+D; JGE
+(_COMP_LABEL_8_SUB)
+@R13
+D=M
+@R14
+D=M-D
+@_COMP_LABEL_8_TRUE
+D; JGT
+@_COMP_LABEL_8_FALSE
+0; JMP
+(_COMP_LABEL_8_TRUE)
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+@_COMP_LABEL_8_END
+0; JMP
+(_COMP_LABEL_8_FALSE)
+@SP
+A=M
+M=0
+@SP
+M=M+1
+(_COMP_LABEL_8_END)
+
+// push constant 0
+@0
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 0
+@0
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// lt
+@SP
+M=M-1
+A=M
+D=M
+@R13
+M=D
+@SP
+M=M-1
+A=M
+D=M
+@R14
+M=D
+@_COMP_LABEL_9_X_NEG
+D; JLT
+@R13
+D=M
+@_COMP_LABEL_9_FALSE
+D; JLT
+@_COMP_LABEL_9_SUB
+0; JMP
+(_COMP_LABEL_9_X_NEG)
+@R13
+D=M
+@_COMP_LABEL_9_TRUE
+D; JGE
+(_COMP_LABEL_9_SUB)
+@R13
+D=M
+@R14
+D=M-D
+@_COMP_LABEL_9_TRUE
+D; JLT
+@_COMP_LABEL_9_FALSE
+0; JMP
+(_COMP_LABEL_9_TRUE)
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+@_COMP_LABEL_9_END
+0; JMP
+(_COMP_LABEL_9_FALSE)
+@SP
+A=M
+M=0
+@SP
+M=M+1
+(_COMP_LABEL_9_END)
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// lt
+@SP
+M=M-1
+A=M
+D=M
+@R13
+M=D
+@SP
+M=M-1
+A=M
+D=M
+@R14
+M=D
+@_COMP_LABEL_10_X_NEG
+D; JLT
+@R13
+D=M
+@_COMP_LABEL_10_FALSE
+D; JLT
+@_COMP_LABEL_10_SUB
+0; JMP
+(_COMP_LABEL_10_X_NEG)
+@R13
+D=M
+@_COMP_LABEL_10_TRUE
+D; JGE
+(_COMP_LABEL_10_SUB)
+@R13
+D=M
+@R14
+D=M-D
+@_COMP_LABEL_10_TRUE
+D; JLT
+@_COMP_LABEL_10_FALSE
+0; JMP
+(_COMP_LABEL_10_TRUE)
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+@_COMP_LABEL_10_END
+0; JMP
+(_COMP_LABEL_10_FALSE)
+@SP
+A=M
+M=0
+@SP
+M=M+1
+(_COMP_LABEL_10_END)
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// gt
+@SP
+M=M-1
+A=M
+D=M
+@R13
+M=D
+@SP
+M=M-1
+A=M
+D=M
+@R14
+M=D
+@_COMP_LABEL_11_X_NEG
+D; JLT
+@R13
+D=M
+@_COMP_LABEL_11_TRUE
+D; JLT
+@_COMP_LABEL_11_SUB
+0; JMP
+(_COMP_LABEL_11_X_NEG)
+@R13
+D=M
+@_COMP_LABEL_11_FALSE
+D; JGE
+(_COMP_LABEL_11_SUB)
+@R13
+D=M
+@R14
+D=M-D
+@_COMP_LABEL_11_TRUE
+D; JGT
+@_COMP_LABEL_11_FALSE
+0; JMP
+(_COMP_LABEL_11_TRUE)
+@SP
+A=M
+M=-1
+@SP
+M=M+1
+@_COMP_LABEL_11_END
+0; JMP
+(_COMP_LABEL_11_FALSE)
+@SP
+A=M
+M=0
+@SP
+M=M+1
+(_COMP_LABEL_11_END)
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 1
+@1
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// add
+@SP
+M=M-1
+A=M
+D=M
+@SP
+M=M-1
+A=M
+A=M
+D=D+A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 1
+@1
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// sub
+@SP
+M=M-1
+A=M
+D=M
+@SP
+M=M-1
+A=M
+A=M
+D=A-D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// push constant 32767
+@32767
+D=A
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// not
+@SP
+M=M-1
+A=M
+D=M
+D=!D
+@SP
+A=M
+M=D
+@SP
+M=M+1
+
+// neg
+@SP
+M=M-1
+A=M
+D=M
+D=-D
+@SP
+A=M
+M=D
+@SP
+M=M+1
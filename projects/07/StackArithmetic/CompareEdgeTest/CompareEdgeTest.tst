@@ -0,0 +1,21 @@
+// File name: projects/07/StackArithmetic/CompareEdgeTest/CompareEdgeTest.tst
+
+load CompareEdgeTest.asm,
+output-file CompareEdgeTest.out,
+compare-to CompareEdgeTest.cmp,
+output-list RAM[0]%D2.6.2 
+        RAM[256]%D2.6.2 RAM[257]%D2.6.2 RAM[258]%D2.6.2 RAM[259]%D2.6.2 RAM[260]%D2.6.2;
+
+set RAM[0] 256,  // initializes the stack pointer
+
+repeat 1000 {    // enough cycles to complete the execution
+  ticktock;
+}
+
+// outputs the stack pointer (RAM[0]) and 
+// the stack contents: RAM[256]-RAM[270]
+output;
+output-list RAM[261]%D2.6.2 RAM[262]%D2.6.2 RAM[263]%D2.6.2 RAM[264]%D2.6.2 RAM[265]%D2.6.2;
+output;
+output-list RAM[266]%D2.6.2 RAM[267]%D2.6.2 RAM[268]%D2.6.2 RAM[269]%D2.6.2 RAM[270]%D2.6.2;
+output;
@@ -0,0 +1,18 @@
+// File name: projects/11/OptimizeTest/OptimizeTest.tst
+
+load,
+output-file OptimizeTest.out,
+compare-to OptimizeTest.cmp,
+output-list RAM[8000]%D2.6.1 RAM[8001]%D2.6.1 RAM[8002]%D2.6.1 RAM[8003]%D2.6.1 RAM[8004]%D2.6.1 RAM[8005]%D2.6.1 RAM[8006]%D2.6.1 RAM[8007]%D2.6.1 RAM[8008]%D2.6.1 RAM[8009]%D2.6.1 RAM[8010]%D2.6.1;
+
+repeat 10000000 {
+  vmstep;
+}
+
+output;
+
+output-list RAM[8011]%D2.6.1 RAM[8012]%D2.6.1 RAM[8013]%D2.6.1 RAM[8014]%D2.6.1 RAM[8015]%D2.6.1 RAM[8016]%D2.6.1;
+output;
+
+output-list RAM[8017]%D2.6.1 RAM[8018]%D2.6.1 RAM[8019]%D2.6.1 RAM[8020]%D2.6.1 RAM[8021]%D2.6.1 RAM[8022]%D2.6.1;
+output;
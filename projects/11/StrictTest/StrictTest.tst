@@ -0,0 +1,12 @@
+// File name: projects/11/StrictTest/StrictTest.tst
+
+load,
+output-file StrictTest.out,
+compare-to StrictTest.cmp,
+output-list RAM[8000]%D2.6.1 RAM[8001]%D2.6.1 RAM[8002]%D2.6.1 RAM[8003]%D2.6.1 RAM[8004]%D2.6.1 RAM[8005]%D2.6.1 RAM[8006]%D2.6.1 RAM[8007]%D2.6.1 RAM[8008]%D2.6.1;
+
+repeat 10000000 {
+  vmstep;
+}
+
+output;
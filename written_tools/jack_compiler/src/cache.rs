@@ -0,0 +1,198 @@
+//! Incremental compilation cache for folder mode.
+//!
+//! Every successfully compiled file gets an entry in `<folder>/.jackcache/<name>.cache`:
+//!
+//! ```text
+//! source 9f2c47e1a0b3d865
+//! options -O
+//! class Square
+//! sub new constructor Square int int int
+//! sub moveUp method void
+//! field x int
+//! dep Memory 3c1d5e0f7a2b4c68
+//! ```
+//!
+//! A file whose source hash is unchanged is not parsed again, its `sub` and `field` lines
+//! stand in for it in the [`ProgramIndex`]. `field` lines only exist with strict typing.
+//! It is only rebuilt when the interface hash of one of its `dep` classes differs from the
+//! one recorded, so editing the body of a subroutine only rebuilds the edited file. Changing
+//! the `options` that affect the generated code rebuilds everything.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    parser_non_xml::{SubroutineType, Type},
+    semantic::{kind_name, type_from_name, type_name, ClassInfo, ProgramIndex, SubroutineInfo},
+};
+
+/// 64 bit FNV-1a, stable across runs and Rust versions unlike `DefaultHasher`
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+fn sub_line(name: &str, sub: &SubroutineInfo) -> String {
+    let mut line = format!(
+        "sub {} {} {}",
+        name,
+        kind_name(sub.kind),
+        type_name(&sub.return_type)
+    );
+    for param in &sub.params {
+        line.push(' ');
+        line.push_str(type_name(param));
+    }
+    line
+}
+
+fn field_line(name: &str, type_type: &Type) -> String {
+    format!("field {} {}", name, type_name(type_type))
+}
+
+/// Hash of all subroutine signatures and accessible fields of a class, independent of their
+/// order
+pub fn interface_hash(info: &ClassInfo) -> u64 {
+    let mut lines: Vec<String> = info
+        .subroutines
+        .iter()
+        .map(|(name, sub)| sub_line(name, sub))
+        .chain(
+            info.fields
+                .iter()
+                .map(|(name, type_type)| field_line(name, type_type)),
+        )
+        .collect();
+    lines.sort();
+    fnv1a(lines.join("\n").as_bytes())
+}
+
+/// Where the cache entry of a .jack file lives
+pub fn entry_path(jack_file: &Path) -> PathBuf {
+    let dir = jack_file.parent().unwrap_or(Path::new(""));
+    let stem = jack_file.file_stem().unwrap_or_default().to_string_lossy();
+    dir.join(".jackcache").join(format!("{}.cache", stem))
+}
+
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub source_hash: u64,
+    /// Command line options the .vm file was generated with
+    pub options: Vec<String>,
+    pub class_name: String,
+    pub subroutines: HashMap<String, SubroutineInfo>,
+    /// Fields with accessors, in declaration order
+    pub fields: Vec<(String, Type)>,
+    /// Interface hash of every class the file depends on, at the time it was compiled
+    pub dependencies: Vec<(String, u64)>,
+}
+
+impl CacheEntry {
+    /// `None` if there is no entry or it cannot be read, the file is then simply rebuilt
+    pub fn load(jack_file: &Path) -> Option<CacheEntry> {
+        let text = fs::read_to_string(entry_path(jack_file)).ok()?;
+
+        let mut entry = CacheEntry {
+            source_hash: 0,
+            options: vec![],
+            class_name: String::new(),
+            subroutines: HashMap::new(),
+            fields: vec![],
+            dependencies: vec![],
+        };
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+            match words.next()? {
+                "source" => entry.source_hash = u64::from_str_radix(words.next()?, 16).ok()?,
+                "options" => entry.options = words.map(str::to_string).collect(),
+                "class" => entry.class_name = words.next()?.to_string(),
+                "sub" => {
+                    let name = words.next()?.to_string();
+                    let kind = match words.next()? {
+                        "constructor" => SubroutineType::Constructor,
+                        "function" => SubroutineType::Function,
+                        "method" => SubroutineType::Method,
+                        _ => return None,
+                    };
+                    let return_type = type_from_name(words.next()?);
+                    let params = words.map(type_from_name).collect();
+                    entry.subroutines.insert(
+                        name,
+                        SubroutineInfo {
+                            kind,
+                            return_type,
+                            params,
+                        },
+                    );
+                }
+                "field" => {
+                    let name = words.next()?.to_string();
+                    entry.fields.push((name, type_from_name(words.next()?)));
+                }
+                "dep" => {
+                    let class_name = words.next()?.to_string();
+                    let hash = u64::from_str_radix(words.next()?, 16).ok()?;
+                    entry.dependencies.push((class_name, hash));
+                }
+                _ => return None,
+            }
+        }
+        if entry.class_name.is_empty() {
+            return None;
+        }
+        Some(entry)
+    }
+
+    pub fn save(&self, jack_file: &Path) -> io::Result<()> {
+        let mut lines = vec![
+            format!("source {:016x}", self.source_hash),
+            format!("options {}", self.options.join(" "))
+                .trim_end()
+                .to_string(),
+            format!("class {}", self.class_name),
+        ];
+        let mut subs: Vec<String> = self
+            .subroutines
+            .iter()
+            .map(|(name, sub)| sub_line(name, sub))
+            .collect();
+        subs.sort();
+        lines.extend(subs);
+        lines.extend(
+            self.fields
+                .iter()
+                .map(|(name, type_type)| field_line(name, type_type)),
+        );
+        for (class_name, hash) in &self.dependencies {
+            lines.push(format!("dep {} {:016x}", class_name, hash));
+        }
+
+        let path = entry_path(jack_file);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, lines.join("\n") + "\n")
+    }
+
+    pub fn remove(jack_file: &Path) {
+        // a missing entry is what we want anyway
+        let _ = fs::remove_file(entry_path(jack_file));
+    }
+
+    /// Whether every dependency still has the interface the file was compiled against
+    pub fn dependencies_unchanged(&self, index: &ProgramIndex) -> bool {
+        self.dependencies.iter().all(|(class_name, hash)| {
+            index
+                .classes
+                .get(class_name)
+                .is_some_and(|info| interface_hash(info) == *hash)
+        })
+    }
+}
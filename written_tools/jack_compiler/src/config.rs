@@ -0,0 +1,102 @@
+//! Per project settings, read from a `jack.config` file next to the .jack files:
+//!
+//! ```text
+//! # operators bind like in C, so 1 + 2 * 3 is 7
+//! precedence = c
+//! # values have to match their declared types
+//! typing = strict
+//! ```
+//!
+//! Options given on the command line win over the file.
+
+use std::{fs, path::Path};
+
+use crate::{error::CompileError, parser_non_xml::Precedence, semantic::Typing, tokenizer::Span};
+
+pub const CONFIG_FILE_NAME: &str = "jack.config";
+
+#[derive(Debug, Clone, Default)]
+pub struct ProjectConfig {
+    pub precedence: Option<Precedence>,
+    pub typing: Option<Typing>,
+}
+
+pub fn precedence_from_name(name: &str) -> Option<Precedence> {
+    match name {
+        "left-to-right" => Some(Precedence::LeftToRight),
+        "c" => Some(Precedence::CLike),
+        _ => None,
+    }
+}
+
+pub fn typing_from_name(name: &str) -> Option<Typing> {
+    match name {
+        "loose" => Some(Typing::Loose),
+        "strict" => Some(Typing::Strict),
+        _ => None,
+    }
+}
+
+impl ProjectConfig {
+    /// Reads the config of the project in `dir`, no file means default settings. Mistakes in
+    /// the file come back rendered like compile errors
+    pub fn load(dir: &Path) -> Result<ProjectConfig, String> {
+        let path = dir.join(CONFIG_FILE_NAME);
+        if !path.is_file() {
+            return Ok(ProjectConfig::default());
+        }
+        let file_name = path.display().to_string();
+        let source = fs::read_to_string(&path)
+            .map_err(|err| format!("error: cannot read {}: {}", file_name, err))?;
+
+        let mut config = ProjectConfig::default();
+        let mut errors = vec![];
+        for (line_idx, line) in source.lines().enumerate() {
+            let content = line.split('#').next().unwrap_or_default();
+            if content.trim().is_empty() {
+                continue;
+            }
+            let span = Span {
+                line: line_idx + 1,
+                col: content.len() - content.trim_start().len() + 1,
+                len: content.trim().chars().count(),
+            };
+            let Some((key, value)) = content.split_once('=') else {
+                errors.push(CompileError::new("expected `key = value`", span));
+                continue;
+            };
+            match (key.trim(), value.trim()) {
+                ("precedence", value) => match precedence_from_name(value) {
+                    Some(precedence) => config.precedence = Some(precedence),
+                    None => errors.push(CompileError::new(
+                        format!(
+                            "unknown precedence `{}`, expected left-to-right or c",
+                            value
+                        ),
+                        span,
+                    )),
+                },
+                ("typing", value) => match typing_from_name(value) {
+                    Some(typing) => config.typing = Some(typing),
+                    None => errors.push(CompileError::new(
+                        format!("unknown typing `{}`, expected loose or strict", value),
+                        span,
+                    )),
+                },
+                (key, _) => errors.push(CompileError::new(
+                    format!("unknown setting `{}`, expected precedence or typing", key),
+                    span,
+                )),
+            }
+        }
+
+        if errors.is_empty() {
+            return Ok(config);
+        }
+        Err(errors
+            .iter()
+            .map(|error| error.render(&file_name, &source))
+            .collect::<Vec<String>>()
+            .join("\n\n"))
+    }
+}
@@ -0,0 +1,141 @@
+//! Values known at compile time: enum values, constants and expressions made only of them.
+//!
+//! The semantic check, the code generator and `-O` all need them and compute them the way
+//! the VM would, with 16 bit wrapping arithmetic.
+
+use std::collections::HashMap;
+
+use crate::{
+    parser_non_xml::{Class, Expression, Term},
+    tokenizer::char_code,
+};
+
+/// Puts the value in place of every name `value_of` knows, for constants and enum values
+pub fn inline_constants(node: &mut Expression, value_of: &dyn Fn(&str) -> Option<i16>) {
+    inline_constants_in_term(&mut node.intial_term, value_of);
+    for (_, node_term) in &mut node.afterwards {
+        inline_constants_in_term(node_term, value_of);
+    }
+}
+
+fn inline_constants_in_term(node: &mut Term, value_of: &dyn Fn(&str) -> Option<i16>) {
+    match node {
+        Term::VarName(name, _) => {
+            if let Some(value) = value_of(name) {
+                *node = constant_term(value);
+            }
+        }
+        Term::ArrayAccess {
+            indices: expressions,
+            ..
+        }
+        | Term::ArrayLiteral {
+            elements: expressions,
+            ..
+        } => {
+            for node_expression in expressions {
+                inline_constants(node_expression, value_of);
+            }
+        }
+        Term::SubroutineCall(sub_call) => {
+            for argument in &mut sub_call.arguments.list {
+                inline_constants(argument, value_of);
+            }
+        }
+        Term::Expression(inner) => inline_constants(inner, value_of),
+        Term::UnaryOp { term, .. } | Term::Store { term, .. } => {
+            inline_constants_in_term(term, value_of)
+        }
+        _ => {}
+    }
+}
+
+/// The variable a name like `p.x` starts with, the name itself for plain variables
+pub fn variable_part(name: &str) -> &str {
+    name.split('.').next().unwrap_or(name)
+}
+
+/// Value of `node` at compile time, with the names `value_of` knows as constants
+pub fn resolved_value(node: &Expression, value_of: &dyn Fn(&str) -> Option<i16>) -> Option<i16> {
+    let mut node = node.clone();
+    inline_constants(&mut node, value_of);
+    constant_value(&node)
+}
+
+/// Values of the enum values and constants of `class` by the name they are used with, like
+/// `Dir.UP` or `WIDTH`. A constant may use the enums and the constants before it, one that
+/// cannot be computed is left out and reported by the semantic check
+pub fn class_constants(class: &Class) -> HashMap<String, i16> {
+    let mut constants = HashMap::new();
+    for enum_dec in &class.enum_dec {
+        for (value, name) in enum_dec.values.iter().enumerate() {
+            constants.insert(format!("{}.{}", enum_dec.name, name), value as i16);
+        }
+    }
+    for const_dec in &class.const_dec {
+        if let Some(value) = resolved_value(&const_dec.value, &|name| constants.get(name).copied())
+        {
+            constants.insert(const_dec.name.clone(), value);
+        }
+    }
+    constants
+}
+
+/// Value of a term made only of constants
+pub fn constant(node: &Term) -> Option<i16> {
+    match node {
+        // 32768 only appears under a unary minus and wraps to the right value there
+        Term::IntegerConstant(int) => Some(*int as i16),
+        Term::CharConstant(character) => Some(char_code(*character) as i16),
+        Term::KeywordConstant(keyword) => match keyword.as_str() {
+            "true" => Some(-1),
+            "false" | "null" => Some(0),
+            _ => None,
+        },
+        Term::UnaryOp { op, term } => constant(term).map(|value| match op.as_str() {
+            "-" => value.wrapping_neg(),
+            _ => !value,
+        }),
+        Term::Expression(inner) => constant_value(inner),
+        _ => None,
+    }
+}
+
+/// Value of an expression made only of constants, as the VM would compute it
+pub fn constant_value(node: &Expression) -> Option<i16> {
+    let mut value = constant(&node.intial_term)?;
+    for (op, term) in &node.afterwards {
+        value = fold(op, value, constant(term)?)?;
+    }
+    Some(value)
+}
+
+/// The shortest term for a value, `-32768` included
+pub fn constant_term(value: i16) -> Term {
+    if value >= 0 {
+        Term::IntegerConstant(value as u64)
+    } else {
+        Term::UnaryOp {
+            op: "-".to_string(),
+            term: Box::new(Term::IntegerConstant(u64::from(value.unsigned_abs()))),
+        }
+    }
+}
+
+/// `left op right` the way the VM computes it, `None` where the OS decides (division by 0)
+pub fn fold(op: &str, left: i16, right: i16) -> Option<i16> {
+    let truth = |value: bool| if value { -1 } else { 0 };
+    Some(match op {
+        "+" => left.wrapping_add(right),
+        "-" => left.wrapping_sub(right),
+        "*" => left.wrapping_mul(right),
+        "/" if right == 0 || left == i16::MIN => return None,
+        "/" => left / right,
+        "&" => left & right,
+        "|" => left | right,
+        "<" => truth(left < right),
+        ">" => truth(left > right),
+        "=" => truth(left == right),
+        _ => unreachable!(),
+    })
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+
+use crate::tokenizer::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    /// Reported, but the file is still compiled
+    Warning,
+}
+
+/// An error in a .jack file, pointing at the offending source code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileError {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+}
+
+impl CompileError {
+    pub fn new(message: impl Into<String>, span: Span) -> CompileError {
+        CompileError {
+            severity: Severity::Error,
+            message: message.into(),
+            span,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>, span: Span) -> CompileError {
+        CompileError {
+            severity: Severity::Warning,
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Renders the error with the source line and a caret under the span:
+    ///
+    /// ```text
+    /// error: expected symbol `;`, found keyword `let`
+    ///  --> Main.jack:12:5
+    ///    |
+    /// 12 |     let y = 2;
+    ///    |     ^^^
+    /// ```
+    pub fn render(&self, file_name: &str, source: &str) -> String {
+        let line_nr = self.span.line.to_string();
+        let gutter = " ".repeat(line_nr.len());
+        let mut buf = vec![
+            match self.severity {
+                Severity::Error => format!("error: {}", self.message),
+                Severity::Warning => format!("warning: {}", self.message),
+            },
+            format!(
+                "{}--> {}:{}:{}",
+                gutter, file_name, self.span.line, self.span.col
+            ),
+        ];
+
+        if let Some(line) = source.lines().nth(self.span.line.saturating_sub(1)) {
+            // keep tabs so the caret lines up with the source line
+            let indent: String = line
+                .chars()
+                .take(self.span.col.saturating_sub(1))
+                .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+                .collect();
+            buf.push(format!("{} |", gutter));
+            buf.push(format!("{} | {}", line_nr, line));
+            buf.push(format!(
+                "{} | {}{}",
+                gutter,
+                indent,
+                "^".repeat(self.span.len.max(1))
+            ));
+        }
+
+        buf.join("\n")
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.span.line, self.span.col, self.message)
+    }
+}
+
+impl std::error::Error for CompileError {}
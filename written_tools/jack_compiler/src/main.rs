@@ -1,107 +1,533 @@
+mod cache;
+mod config;
+mod constants;
+mod error;
+mod optimize;
 mod parser_non_xml;
+mod semantic;
 mod tokenizer;
+mod xml;
 
 use std::{
     env,
     fs::{read_to_string, File},
     io::Write,
-    path::Path,
+    path::{Path, PathBuf},
+    process,
     time::Instant,
 };
 
-use crate::parser_non_xml::{Parser, CodeGenerator};
-use crate::tokenizer::Tokenizer;
+use crate::cache::{fnv1a, interface_hash, CacheEntry};
+use crate::config::{precedence_from_name, typing_from_name, ProjectConfig, CONFIG_FILE_NAME};
+use crate::error::CompileError;
+use crate::optimize::optimize_class;
+use crate::parser_non_xml::{Class, CodeGenerator, Parser, Precedence};
+use crate::semantic::{ProgramIndex, Typing};
+use crate::tokenizer::{Dialect, Tokenizer};
+use crate::xml::class_to_xml;
+
+const USAGE: &str = "Jack Compiler by Iquiji requires:
+
+jack_compiler FilePath/FolderPath! [more paths...] [--emit tokens|parse-xml|vm|all] [-O]
+              [--extended] [--precedence left-to-right|c] [--typing loose|strict]
+
+--emit  comma separated outputs written next to every .jack file: tokens (<name>.T.xml),
+        parse-xml (<name>.xml), vm (<name>.vm) or all, default tokens,vm
+-O      optimize the VM code: fold constants, drop dead branches, replace multiplications
+        and divisions by powers of two and compute repeated subexpressions once
+--extended
+        compile extended Jack: adds for (init; condition; step) { }, break, continue,
+        else if, switch, let x += 1 and friends, 'a' chars, 0x1F and 0b1010 integers,
+        escapes like \\n in strings and class level const int N = 1; and enum E { A, B }.
+        The generated VM code still runs on the standard tools
+--precedence
+        left-to-right evaluates operators strictly from left to right as Jack requires,
+        c binds * / before + - before < > before = before & before |. Expressions that
+        differ between the two get a warning either way
+--typing
+        loose checks types like the Jack specification: int, char and boolean mix and an
+        int may hold an object. strict wants values of their declared type, a char passes
+        for an int and constants for a char, conditions are boolean, only Arrays are
+        indexed. Strict classes get accessors for their fields, extended Jack in other
+        classes then reads and writes them as obj.field
+
+All given files, together with the other .jack files in their folders and the Jack OS,
+are compiled as one program: every call is resolved against that whole program.
+
+Files found through a folder are cached in <folder>/.jackcache and only compiled again
+when they or the signatures of the classes they use change.
+
+A jack.config file next to the .jack files sets the precedence and typing of that project
+with lines like `precedence = c` and `typing = strict`, the command line wins over it.";
+
+/// Which files get written for every compiled .jack file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Emit {
+    tokens: bool,
+    parse_xml: bool,
+    vm: bool,
+}
+impl Emit {
+    fn from_list(list: &str) -> Option<Emit> {
+        let mut emit = Emit {
+            tokens: false,
+            parse_xml: false,
+            vm: false,
+        };
+        for output in list.split(',') {
+            match output {
+                "tokens" => emit.tokens = true,
+                "parse-xml" => emit.parse_xml = true,
+                "vm" => emit.vm = true,
+                "all" => {
+                    emit.tokens = true;
+                    emit.parse_xml = true;
+                    emit.vm = true;
+                }
+                _ => return None,
+            }
+        }
+        Some(emit)
+    }
+}
+
+/// Options that change the generated code of a file
+#[derive(Debug, Clone, Copy, Default)]
+struct Options {
+    optimize: bool,
+    dialect: Dialect,
+    precedence: Precedence,
+    typing: Typing,
+}
+impl Options {
+    /// How the options are recorded in the cache, a cached file built with others is rebuilt
+    fn to_words(self) -> Vec<String> {
+        let mut words = vec![];
+        if self.optimize {
+            words.push("-O".to_string());
+        }
+        if self.dialect == Dialect::Extended {
+            words.push("--extended".to_string());
+        }
+        if self.precedence == Precedence::CLike {
+            words.push("--precedence".to_string());
+            words.push("c".to_string());
+        }
+        if self.typing == Typing::Strict {
+            words.push("--typing".to_string());
+            words.push("strict".to_string());
+        }
+        words
+    }
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let start_start = Instant::now();
 
     let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        println!("Jack Compiler by Iquiji requires:\n\njack_compiler FilePath/FolderPath!");
-        return Ok(());
-    };
 
-    let path = Path::new(&args[1]);
+    let mut paths = vec![];
+    let mut emit = Emit {
+        tokens: true,
+        parse_xml: false,
+        vm: true,
+    };
+    let mut options = Options::default();
+    let mut cli_precedence = None;
+    let mut cli_typing = None;
+    let mut args_iter = args.iter().skip(1);
+    while let Some(arg) = args_iter.next() {
+        if arg == "--emit" {
+            match args_iter.next().and_then(|list| Emit::from_list(list)) {
+                Some(new_emit) => emit = new_emit,
+                None => {
+                    println!("{}", USAGE);
+                    return Ok(());
+                }
+            }
+        } else if arg == "-O" {
+            options.optimize = true;
+        } else if arg == "--extended" {
+            options.dialect = Dialect::Extended;
+        } else if arg == "--precedence" {
+            match args_iter.next().and_then(|name| precedence_from_name(name)) {
+                Some(precedence) => cli_precedence = Some(precedence),
+                None => {
+                    println!("{}", USAGE);
+                    return Ok(());
+                }
+            }
+        } else if arg == "--typing" {
+            match args_iter.next().and_then(|name| typing_from_name(name)) {
+                Some(typing) => cli_typing = Some(typing),
+                None => {
+                    println!("{}", USAGE);
+                    return Ok(());
+                }
+            }
+        } else if arg.starts_with('-') {
+            println!("{}", USAGE);
+            return Ok(());
+        } else {
+            paths.push(Path::new(arg));
+        }
+    }
+    if paths.is_empty() {
+        println!("{}", USAGE);
+        return Ok(());
+    }
 
-    if path.is_dir() {
-        let dir = path.read_dir()?;
+    // folders contribute all their .jack files, which may use the cache, files are taken as
+    // they are. Both get the settings of the jack.config in their folder
+    let mut all_files_to_process: Vec<(PathBuf, bool, Options)> = vec![];
+    for path in paths {
+        let project_dir = if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(Path::new(""))
+        };
+        let config = match ProjectConfig::load(project_dir) {
+            Ok(config) => config,
+            Err(message) => {
+                eprintln!("{}\n", message);
+                eprintln!("{} has to be fixed first", CONFIG_FILE_NAME);
+                process::exit(1);
+            }
+        };
+        let options = Options {
+            precedence: cli_precedence.or(config.precedence).unwrap_or_default(),
+            typing: cli_typing.or(config.typing).unwrap_or_default(),
+            ..options
+        };
 
-        let base_path = path;
-        let mut all_files_to_process = vec![];
+        if path.is_dir() {
+            let mut files_in_dir = vec![];
+            for file_path in path.read_dir()? {
+                let file_path = file_path?.path();
+                if file_path.is_file()
+                    && file_path.extension().and_then(|ext| ext.to_str()) == Some("jack")
+                {
+                    files_in_dir.push(file_path);
+                }
+            }
+            files_in_dir.sort();
+            all_files_to_process.extend(files_in_dir.into_iter().map(|file| (file, true, options)));
+        } else {
+            all_files_to_process.push((path.to_path_buf(), false, options));
+        }
+    }
 
-        for file_path in dir {
-            let file_path = file_path?.path();
-            if file_path.is_dir(){
+    let mut failed_files = 0;
+    let mut parsed_files = vec![];
+    let mut cached_files = vec![];
+    for (file_path, use_cache, options) in all_files_to_process {
+        if use_cache && emit.vm {
+            if let Some(entry) = unchanged_entry(&file_path, emit, options)? {
+                println!("\n+ Unchanged File: {:?}", file_path);
+                cached_files.push((file_path, entry, options));
                 continue;
             }
-            if file_path.extension().unwrap().to_str().unwrap() == "jack" {
-                // only handle .vm files
-                let temp = file_path.file_name().unwrap().to_str().unwrap();
-                all_files_to_process.push(temp.to_string());
-            }
         }
+        match parse_file(&file_path, emit, options, use_cache)? {
+            FrontEnd::Parsed(parsed_file) => parsed_files.push(parsed_file),
+            FrontEnd::TokensOnly => {}
+            FrontEnd::Failed => failed_files += 1,
+        }
+    }
+    if emit.vm {
+        failed_files += check_and_generate(
+            parsed_files,
+            cached_files,
+            emit,
+            options.dialect,
+            cli_typing,
+        )?;
+    }
 
-        for file_name in all_files_to_process {
-            println!(
-                "\n+ Proccesing File: {:?}",
-                &base_path.join(file_name.clone())
-            );
-
-            let out_file_path = base_path.join(&file_name).with_extension("T.xml");
+    println!(
+        "\nJack Compiler Total Time Used: {:?}",
+        start_start.elapsed()
+    );
 
-            let read_in_file = read_to_string(&base_path.join(&file_name))?;
+    if failed_files > 0 {
+        eprintln!("\n{} file(s) failed to compile", failed_files);
+        process::exit(1);
+    }
 
-            let duration = start_start.elapsed();
-            println!("- Read in vm file!: {:?}", duration);
-            let start = Instant::now();
+    Ok(())
+}
 
-            let tokenizer = Tokenizer::from_string(read_in_file);
+/// A .jack file that made it through the parser
+struct ParsedFile {
+    path: PathBuf,
+    source: String,
+    class: Class,
+    options: Options,
+    /// Whether a cache entry is written for it
+    use_cache: bool,
+}
 
-            let duration = start.elapsed();
-            println!("- Tokenizing finished for file!: {:?}", duration);
-            let start = Instant::now();
+enum FrontEnd {
+    Parsed(ParsedFile),
+    /// Only tokens were requested, nothing was parsed
+    TokensOnly,
+    Failed,
+}
 
-            let test_t_xml = tokenizer.to_xml_string();
+fn print_errors(file_path: &Path, source: &str, errors: &[CompileError]) {
+    let file_name = file_path.display().to_string();
+    for error in errors {
+        eprintln!("{}\n", error.render(&file_name, source));
+    }
+}
 
-            let mut file = File::create(out_file_path.clone())?;
-            file.write_all(test_t_xml.as_bytes())?;
+/// Tokenizes and parses one .jack file and writes the token and parse tree XML selected by
+/// `emit`. Compile errors are printed
+fn parse_file(
+    file_path: &Path,
+    emit: Emit,
+    options: Options,
+    use_cache: bool,
+) -> Result<FrontEnd, Box<dyn std::error::Error>> {
+    println!("\n+ Proccesing File: {:?}", file_path);
+    let start_start = Instant::now();
 
-            let parsed_file = Parser::from_tokenizer(tokenizer).compileClass();
+    let read_in_file = read_to_string(file_path)?;
 
-            let duration = start.elapsed();
-            println!("- Parsing finished for file!: {:?}", duration);
-            let start = Instant::now();
+    let duration = start_start.elapsed();
+    println!("- Read in jack file!: {:?}", duration);
+    let start = Instant::now();
 
-            let compiled_file = CodeGenerator::new().to_vm_code(parsed_file);
+    let tokenizer = match Tokenizer::from_string(read_in_file.clone(), options.dialect) {
+        Ok(tokenizer) => tokenizer,
+        Err(errors) => {
+            print_errors(file_path, &read_in_file, &errors);
+            return Ok(FrontEnd::Failed);
+        }
+    };
 
-            let duration = start.elapsed();
-            println!("- Compiling finished for file!: {:?}", duration);
-            let start = Instant::now();
+    let duration = start.elapsed();
+    println!("- Tokenizing finished for file!: {:?}", duration);
 
-            let mut file = File::create(base_path.join(&file_name).with_extension("vm"))?;
-            file.write_all(compiled_file.as_bytes())?;
+    if emit.tokens {
+        let mut file = File::create(file_path.with_extension("T.xml"))?;
+        file.write_all(tokenizer.to_xml_string().as_bytes())?;
+    }
+    if !(emit.parse_xml || emit.vm) {
+        return Ok(FrontEnd::TokensOnly);
+    }
+    let start = Instant::now();
 
-            let duration = start.elapsed();
-            println!("- Flush VM Code to File!: {:?}", duration);
+    let mut parser = Parser::from_tokenizer(tokenizer, options.dialect, options.precedence);
+    let parse_result = parser.compileClass();
+    print_errors(file_path, &read_in_file, &parser.warnings);
+    let parsed_file = match parse_result {
+        Ok(parsed_file) => parsed_file,
+        Err(errors) => {
+            print_errors(file_path, &read_in_file, &errors);
+            return Ok(FrontEnd::Failed);
         }
-    } else {
-        let in_file_path = path;
-        let out_file_path = path.with_extension("T.xml");
+    };
 
-        let read_in_file = read_to_string(in_file_path)?;
+    let duration = start.elapsed();
+    println!("- Parsing finished for file!: {:?}", duration);
 
-        let tokenizer = Tokenizer::from_string(read_in_file);
+    if emit.parse_xml {
+        let mut file = File::create(file_path.with_extension("xml"))?;
+        file.write_all(class_to_xml(&parsed_file).as_bytes())?;
+    }
 
-        let test_t_xml = tokenizer.to_xml_string();
+    Ok(FrontEnd::Parsed(ParsedFile {
+        path: file_path.to_path_buf(),
+        source: read_in_file,
+        class: parsed_file,
+        options,
+        use_cache,
+    }))
+}
+
+/// The cache entry of a file if the file did not change since, it was compiled with the same
+/// options and all selected outputs are still there
+fn unchanged_entry(
+    file_path: &Path,
+    emit: Emit,
+    options: Options,
+) -> Result<Option<CacheEntry>, Box<dyn std::error::Error>> {
+    let Some(entry) = CacheEntry::load(file_path) else {
+        return Ok(None);
+    };
+    let outputs_exist = file_path.with_extension("vm").is_file()
+        && (!emit.tokens || file_path.with_extension("T.xml").is_file())
+        && (!emit.parse_xml || file_path.with_extension("xml").is_file());
+    if !outputs_exist
+        || entry.options != options.to_words()
+        || fnv1a(read_to_string(file_path)?.as_bytes()) != entry.source_hash
+    {
+        return Ok(None);
+    }
+    Ok(Some(entry))
+}
+
+/// Classes of the .jack files next to the compiled ones that were not passed in themselves,
+/// they belong to the same program. Files that do not parse are left out silently. Each
+/// comes with the typing of its folder, which decides whether it has field accessors
+fn sibling_classes(
+    compiled_files: &[&Path],
+    dialect: Dialect,
+    cli_typing: Option<Typing>,
+) -> Vec<(Class, Typing)> {
+    let compiled: Vec<PathBuf> = compiled_files
+        .iter()
+        .filter_map(|path| path.canonicalize().ok())
+        .collect();
+    let mut dirs: Vec<PathBuf> = compiled
+        .iter()
+        .filter_map(|path| path.parent().map(Path::to_path_buf))
+        .collect();
+    dirs.sort();
+    dirs.dedup();
 
-        let mut file = File::create(out_file_path)?;
-        file.write_all(test_t_xml.as_bytes())?;
+    let mut sibling_paths: Vec<(PathBuf, Typing)> = vec![];
+    for dir in dirs {
+        let config = ProjectConfig::load(&dir).unwrap_or_default();
+        let typing = cli_typing.or(config.typing).unwrap_or_default();
+        let Ok(entries) = dir.read_dir() else {
+            continue;
+        };
+        sibling_paths.extend(
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("jack"))
+                .filter(|path| !compiled.contains(path))
+                .map(|path| (path, typing)),
+        );
     }
+    sibling_paths.sort_by(|(left, _), (right, _)| left.cmp(right));
 
+    sibling_paths
+        .iter()
+        .filter_map(|(path, typing)| {
+            let source = read_to_string(path).ok()?;
+            let tokenizer = Tokenizer::from_string(source, dialect).ok()?;
+            // only the signatures are needed, they do not depend on the precedence
+            Parser::from_tokenizer(tokenizer, dialect, Precedence::LeftToRight)
+                .compileClass()
+                .ok()
+                .map(|class| (class, *typing))
+        })
+        .collect()
+}
+
+/// Checks all parsed files against each other and writes the .vm file of every file without
+/// errors. Cached files only take part with their signatures unless one of their
+/// dependencies changed. Returns how many files failed
+fn check_and_generate(
+    mut parsed_files: Vec<ParsedFile>,
+    cached_files: Vec<(PathBuf, CacheEntry, Options)>,
+    emit: Emit,
+    dialect: Dialect,
+    cli_typing: Option<Typing>,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let start = Instant::now();
+
+    let mut index = ProgramIndex::new();
+    let compiled_files: Vec<&Path> = parsed_files
+        .iter()
+        .map(|parsed_file| parsed_file.path.as_path())
+        .chain(cached_files.iter().map(|(path, _, _)| path.as_path()))
+        .collect();
+    for (class, typing) in sibling_classes(&compiled_files, dialect, cli_typing) {
+        index.add_class(&class, typing);
+    }
+    for (_, entry, _) in &cached_files {
+        index.add_interface(
+            &entry.class_name,
+            entry.subroutines.clone(),
+            entry.fields.clone(),
+        );
+    }
+    let mut errors_per_file: Vec<Vec<CompileError>> = parsed_files
+        .iter()
+        .map(|parsed_file| index.add_class(&parsed_file.class, parsed_file.options.typing))
+        .collect();
+
+    let mut failed_files = 0;
+    for (file_path, entry, options) in cached_files {
+        if entry.dependencies_unchanged(&index) {
+            continue;
+        }
+        println!("\n+ Dependencies of {:?} changed", file_path);
+        match parse_file(&file_path, emit, options, true)? {
+            FrontEnd::Parsed(parsed_file) => {
+                parsed_files.push(parsed_file);
+                errors_per_file.push(vec![]);
+            }
+            _ => failed_files += 1,
+        }
+    }
+
+    let duration = start.elapsed();
     println!(
-        "\nJack Compiler Total Time Used: {:?}",
-        start_start.elapsed()
+        "\n- Indexing {} classes finished!: {:?}",
+        index.classes.len(),
+        duration
     );
 
-    Ok(())
+    for (parsed_file, errors) in parsed_files.into_iter().zip(&mut errors_per_file) {
+        println!("\n+ Generating VM code for File: {:?}", parsed_file.path);
+        let start = Instant::now();
+
+        let check = index.check_class(&parsed_file.class, parsed_file.options.typing);
+        errors.extend(check.errors);
+        if !errors.is_empty() {
+            print_errors(&parsed_file.path, &parsed_file.source, errors);
+            if parsed_file.use_cache {
+                CacheEntry::remove(&parsed_file.path);
+            }
+            failed_files += 1;
+            continue;
+        }
+        let cache_entry = parsed_file.use_cache.then(|| CacheEntry {
+            source_hash: fnv1a(parsed_file.source.as_bytes()),
+            options: parsed_file.options.to_words(),
+            class_name: parsed_file.class.name.clone(),
+            subroutines: index.classes[&parsed_file.class.name].subroutines.clone(),
+            fields: index.classes[&parsed_file.class.name].fields.clone(),
+            dependencies: check
+                .dependencies
+                .iter()
+                .filter_map(|dep| {
+                    let info = index.classes.get(dep)?;
+                    Some((dep.clone(), interface_hash(info)))
+                })
+                .collect(),
+        });
+
+        let duration = start.elapsed();
+        println!("- Checking finished for file!: {:?}", duration);
+        let start = Instant::now();
+
+        let mut class = parsed_file.class;
+        if parsed_file.options.optimize {
+            optimize_class(&mut class);
+        }
+        let compiled_file = CodeGenerator::new(&index).to_vm_code(class);
+
+        let duration = start.elapsed();
+        println!("- Compiling finished for file!: {:?}", duration);
+        let start = Instant::now();
+
+        let mut file = File::create(parsed_file.path.with_extension("vm"))?;
+        file.write_all(compiled_file.as_bytes())?;
+
+        // only once the .vm file is written, so an interrupted run is simply redone
+        if let Some(cache_entry) = cache_entry {
+            cache_entry.save(&parsed_file.path)?;
+        }
+
+        let duration = start.elapsed();
+        println!("- Flush VM Code to File!: {:?}", duration);
+    }
+
+    Ok(failed_files)
 }
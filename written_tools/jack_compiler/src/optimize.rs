@@ -0,0 +1,593 @@
+//! `-O`: rewrites of the checked AST right before code generation.
+//!
+//! - constant folding with the 16 bit wrapping arithmetic of the Hack platform
+//! - `x + 0`, `x - 0`, `x | 0`, `x * 1` and `x / 1` are dropped
+//! - `x * 2^k` becomes `k` doublings instead of a `Math.multiply` call
+//! - `x / 2^k` calls `divide$<2^k>`, a function added to the class once per divisor, instead
+//!   of `Math.divide`. There is no shift in the VM language, so it tests the bits of `x` from
+//!   bit `k` on one by one, about 8 VM commands per bit. Inlined, every division would take
+//!   up to 130 commands of ROM, shared it only costs a call, still far cheaper than the loop
+//!   in `Math.divide`
+//! - `if`/`while` with a constant condition keep only the branch that can run
+//! - common subexpressions inside expressions without calls are computed once
+//!
+//! Reused values live in the temp segment: doublings use `temp 1`, common subexpressions
+//! `temp 2` to `temp 7`. `temp 0` stays with the code generator. A called subroutine may
+//! use the same slots, so values are only kept in temp while no call can happen: a doubling
+//! stores and loads back to back and common subexpressions are only shared within a
+//! call-free expression.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    constants::{
+        class_constants, constant, constant_term, constant_value, fold, inline_constants,
+        variable_part,
+    },
+    parser_non_xml::{
+        Class, Expression, ExpressionList, Let, ParameterList, Return, Statement, SubroutineBody,
+        SubroutineCall, SubroutineDec, SubroutineType, Term, Type,
+    },
+    tokenizer::{char_code, Span},
+};
+
+const DOUBLING_SLOT: u8 = 1;
+const FIRST_CSE_SLOT: u8 = 2;
+const LAST_CSE_SLOT: u8 = 7;
+
+pub fn optimize_class(class: &mut Class) {
+    let mut divisors = BTreeSet::new();
+    let mut class_constants = class_constants(class);
+    // variables hide constants of the same name, `p.x` of a variable p is a field
+    let class_vars: Vec<String> = class
+        .var_dec
+        .iter()
+        .flat_map(|var_dec| var_dec.variable_names.clone())
+        .collect();
+    class_constants.retain(|name, _| !class_vars.iter().any(|var| var == variable_part(name)));
+    for sub in &mut class.sub_dec {
+        let mut constants = class_constants.clone();
+        let locals: Vec<&String> = sub
+            .parameter_list
+            .list
+            .iter()
+            .map(|(_, name)| name)
+            .chain(
+                sub.body
+                    .variable_declaration
+                    .iter()
+                    .flat_map(|var_dec| &var_dec.variable_names),
+            )
+            .collect();
+        constants.retain(|name, _| !locals.iter().any(|var| *var == variable_part(name)));
+
+        let body = std::mem::take(&mut sub.body.body);
+        sub.body.body = statements(body, &constants, &mut divisors);
+    }
+    for k in divisors {
+        class.sub_dec.push(divide_function(k, class.span));
+    }
+}
+
+fn statements(
+    statements_in: Vec<Statement>,
+    constants: &HashMap<String, i16>,
+    divisors: &mut BTreeSet<u32>,
+) -> Vec<Statement> {
+    let mut buf = vec![];
+    for statement in statements_in {
+        match statement {
+            Statement::Let(mut let_statement) => {
+                assignment(&mut let_statement, constants, divisors);
+                buf.push(Statement::Let(let_statement));
+            }
+            Statement::If(mut if_statement) => {
+                expression(&mut if_statement.condition, constants, divisors);
+                // the condition is negated and jumped on, so only true (-1) runs the if branch
+                match constant_value(&if_statement.condition) {
+                    Some(-1) => buf.extend(statements(if_statement.if_true, constants, divisors)),
+                    Some(_) => buf.extend(statements(
+                        if_statement.else_part.unwrap_or_default(),
+                        constants,
+                        divisors,
+                    )),
+                    None => {
+                        if_statement.if_true =
+                            statements(if_statement.if_true, constants, divisors);
+                        if_statement.else_part = if_statement
+                            .else_part
+                            .map(|else_part| statements(else_part, constants, divisors));
+                        buf.push(Statement::If(if_statement));
+                    }
+                }
+            }
+            Statement::While(mut while_statement) => {
+                expression(&mut while_statement.condition, constants, divisors);
+                if matches!(constant_value(&while_statement.condition), Some(value) if value != -1)
+                {
+                    // never entered
+                    continue;
+                }
+                while_statement.if_true = statements(while_statement.if_true, constants, divisors);
+                buf.push(Statement::While(while_statement));
+            }
+            Statement::For(mut for_statement) => {
+                if let Some(init) = &mut for_statement.init {
+                    assignment(init, constants, divisors);
+                }
+                if let Some(condition) = &mut for_statement.condition {
+                    expression(condition, constants, divisors);
+                    match constant_value(condition) {
+                        // loops until a break, like no condition at all
+                        Some(-1) => for_statement.condition = None,
+                        // never entered, only the init is left
+                        Some(_) => {
+                            buf.extend(for_statement.init.map(Statement::Let));
+                            continue;
+                        }
+                        None => {}
+                    }
+                }
+                if let Some(step) = &mut for_statement.step {
+                    assignment(step, constants, divisors);
+                }
+                for_statement.body = statements(for_statement.body, constants, divisors);
+                buf.push(Statement::For(for_statement));
+            }
+            Statement::Break | Statement::Continue => buf.push(statement),
+            Statement::Switch(mut switch_statement) => {
+                // case values stay as they are, the code generator only pushes their value
+                expression(&mut switch_statement.value, constants, divisors);
+                for arm in &mut switch_statement.arms {
+                    arm.body = statements(std::mem::take(&mut arm.body), constants, divisors);
+                }
+                buf.push(Statement::Switch(switch_statement));
+            }
+            Statement::Do(mut do_statement) => {
+                call(&mut do_statement.call, constants, divisors);
+                buf.push(Statement::Do(do_statement));
+            }
+            Statement::Return(mut return_statement) => {
+                if let Some(value) = &mut return_statement.value {
+                    expression(value, constants, divisors);
+                }
+                buf.push(Statement::Return(return_statement));
+            }
+        }
+    }
+    buf
+}
+
+fn assignment(node: &mut Let, constants: &HashMap<String, i16>, divisors: &mut BTreeSet<u32>) {
+    for index in &mut node.indices {
+        expression(index, constants, divisors);
+    }
+    expression(&mut node.bind_to, constants, divisors);
+}
+
+fn expression(
+    node: &mut Expression,
+    constants: &HashMap<String, i16>,
+    divisors: &mut BTreeSet<u32>,
+) {
+    inline_constants(node, &|name| constants.get(name).copied());
+    simplify(node, divisors);
+    eliminate_common_subexpressions(node);
+}
+
+fn call(node: &mut SubroutineCall, constants: &HashMap<String, i16>, divisors: &mut BTreeSet<u32>) {
+    for argument in &mut node.arguments.list {
+        expression(argument, constants, divisors);
+    }
+}
+
+/// `k` for a constant `2^k` with `k >= 1`
+fn power_of_two(node: &Term) -> Option<u32> {
+    match constant(node)? {
+        value if value > 1 && value.count_ones() == 1 => Some(value.trailing_zeros()),
+        _ => None,
+    }
+}
+
+/// `term * 2^k` as `k` times `value + value`, keeping the value in temp in between
+fn doubled(mut node: Term, k: u32, span: Span) -> Term {
+    for _ in 0..k {
+        node = Term::Expression(Box::new(Expression {
+            intial_term: Term::Store {
+                slot: DOUBLING_SLOT,
+                term: Box::new(node),
+            },
+            afterwards: vec![("+".to_string(), Term::Load(DOUBLING_SLOT))],
+            span,
+        }));
+    }
+    node
+}
+
+/// `value / 2^k` as a call of the function [`divide_function`] adds for `k`
+fn divided(value: Expression, k: u32, span: Span) -> Term {
+    Term::SubroutineCall(SubroutineCall {
+        obj_name: None,
+        subroutine_name: format!("divide${}", 1 << k),
+        arguments: ExpressionList { list: vec![value] },
+        span,
+    })
+}
+
+/// `function int divide$2^k(int value)`, rounding towards 0 like `Math.divide`. Bit `i` of
+/// the value adds `2^(i-k)` for `i` from `k` to 14 and the sign bit `-2^(15-k)`. That rounds
+/// down, so a negative value with any of the lower `k` bits set gets 1 added
+fn divide_function(k: u32, span: Span) -> SubroutineDec {
+    let expression = |intial_term, afterwards: Vec<(&str, Term)>| Expression {
+        intial_term,
+        afterwards: afterwards
+            .into_iter()
+            .map(|(op, node_term)| (op.to_string(), node_term))
+            .collect(),
+        span,
+    };
+    let value = || Term::VarName("value".to_string(), span);
+    // `(value & 2^i) > 0` is true for a set bit, only bit 15 makes a value negative
+    let bit = |i: u32| {
+        Term::Expression(Box::new(expression(
+            value(),
+            vec![
+                ("&", constant_term(1 << i)),
+                (">", Term::IntegerConstant(0)),
+                ("&", constant_term(1 << (i - k))),
+            ],
+        )))
+    };
+    let negative = |mask| {
+        Term::Expression(Box::new(expression(
+            value(),
+            vec![("<", Term::IntegerConstant(0)), ("&", mask)],
+        )))
+    };
+    let lower_bits = Term::Expression(Box::new(expression(
+        value(),
+        vec![
+            ("&", constant_term((1 << k) - 1)),
+            (">", Term::IntegerConstant(0)),
+        ],
+    )));
+
+    let mut afterwards: Vec<(&str, Term)> = (k + 1..15).map(|i| ("+", bit(i))).collect();
+    afterwards.push(("+", negative(constant_term(-(1 << (15 - k))))));
+    afterwards.push((
+        "+",
+        negative(Term::Expression(Box::new(expression(
+            lower_bits,
+            vec![("&", Term::IntegerConstant(1))],
+        )))),
+    ));
+    SubroutineDec {
+        subroutine_type: SubroutineType::Function,
+        return_type: Type::Int,
+        name: format!("divide${}", 1 << k),
+        parameter_list: ParameterList {
+            list: vec![(Type::Int, "value".to_string())],
+            span,
+        },
+        body: SubroutineBody {
+            variable_declaration: vec![],
+            body: vec![Statement::Return(Return {
+                value: Some(expression(bit(k), afterwards)),
+                span,
+            })],
+        },
+        span,
+    }
+}
+
+/// Folds constants, drops neutral operations and reduces multiplications and divisions by
+/// powers of two. Jack has no precedence, everything left of an operator is its left operand
+fn simplify(node: &mut Expression, divisors: &mut BTreeSet<u32>) {
+    term(&mut node.intial_term, divisors);
+    let mut afterwards = vec![];
+    for (op, mut right) in std::mem::take(&mut node.afterwards) {
+        term(&mut right, divisors);
+
+        if afterwards.is_empty() {
+            if let (Some(left_value), Some(right_value)) =
+                (constant(&node.intial_term), constant(&right))
+            {
+                if let Some(value) = fold(&op, left_value, right_value) {
+                    node.intial_term = constant_term(value);
+                    continue;
+                }
+            }
+        }
+
+        match (op.as_str(), constant(&right)) {
+            ("+" | "-" | "|", Some(0)) | ("*" | "/", Some(1)) => continue,
+            _ => {}
+        }
+
+        if op == "*" && afterwards.is_empty() {
+            // `2^k * right` with nothing before it, multiplication commutes
+            if let Some(k) = power_of_two(&node.intial_term) {
+                node.intial_term = doubled(right, k, node.span);
+                continue;
+            }
+        }
+        if op == "*" || op == "/" {
+            if let Some(k) = power_of_two(&right) {
+                let left = Expression {
+                    intial_term: std::mem::replace(&mut node.intial_term, Term::IntegerConstant(0)),
+                    afterwards: std::mem::take(&mut afterwards),
+                    span: node.span,
+                };
+                node.intial_term = if op == "*" {
+                    let left = if left.afterwards.is_empty() {
+                        left.intial_term
+                    } else {
+                        Term::Expression(Box::new(left))
+                    };
+                    doubled(left, k, node.span)
+                } else {
+                    divisors.insert(k);
+                    divided(left, k, node.span)
+                };
+                continue;
+            }
+        }
+
+        afterwards.push((op, right));
+    }
+    node.afterwards = afterwards;
+}
+
+fn term(node: &mut Term, divisors: &mut BTreeSet<u32>) {
+    match node {
+        Term::ArrayAccess {
+            indices: expressions,
+            ..
+        }
+        | Term::ArrayLiteral {
+            elements: expressions,
+            ..
+        } => expressions
+            .iter_mut()
+            .for_each(|node_expression| simplify(node_expression, divisors)),
+        Term::SubroutineCall(sub_call) => {
+            for argument in &mut sub_call.arguments.list {
+                simplify(argument, divisors);
+            }
+        }
+        Term::Expression(inner) => {
+            simplify(inner, divisors);
+            // parentheses around a single term do nothing
+            if inner.afterwards.is_empty() {
+                let inner_term =
+                    std::mem::replace(&mut inner.intial_term, Term::IntegerConstant(0));
+                *node = inner_term;
+            }
+        }
+        Term::UnaryOp { term: inner, .. } => {
+            term(inner, divisors);
+            if let Some(value) = constant(node) {
+                *node = constant_term(value);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn call_free_term(node: &Term) -> bool {
+    match node {
+        // strings are built with String.new and String.appendChar, array literals with
+        // Array.new
+        Term::SubroutineCall(_) | Term::StringConstant(_) | Term::ArrayLiteral { .. } => false,
+        // constants are inlined by now, so `p.x` is a field read through its accessor
+        Term::VarName(name, _) => !name.contains('.'),
+        Term::ArrayAccess {
+            array_name,
+            indices,
+            ..
+        } => !array_name.contains('.') && indices.iter().all(call_free),
+        Term::Expression(inner) => call_free(inner),
+        Term::UnaryOp { term, .. } | Term::Store { term, .. } => call_free_term(term),
+        _ => true,
+    }
+}
+
+/// `*` and `/` are calls into Math as well
+fn call_free(node: &Expression) -> bool {
+    call_free_term(&node.intial_term)
+        && node
+            .afterwards
+            .iter()
+            .all(|(op, term)| op != "*" && op != "/" && call_free_term(term))
+}
+
+/// Finds the largest call-free expressions and shares common subexpressions in each
+fn eliminate_common_subexpressions(node: &mut Expression) {
+    if call_free(node) {
+        let mut counts = HashMap::new();
+        count_expression(node, &mut counts);
+        let mut cse = Cse {
+            counts,
+            slots: HashMap::new(),
+            next_slot: FIRST_CSE_SLOT,
+        };
+        cse.expression(node);
+        return;
+    }
+    for node_term in std::iter::once(&mut node.intial_term)
+        .chain(node.afterwards.iter_mut().map(|(_, node_term)| node_term))
+    {
+        cse_units_in_term(node_term);
+    }
+}
+
+fn cse_units_in_term(node: &mut Term) {
+    match node {
+        Term::ArrayAccess {
+            indices: expressions,
+            ..
+        }
+        | Term::ArrayLiteral {
+            elements: expressions,
+            ..
+        } => expressions
+            .iter_mut()
+            .for_each(eliminate_common_subexpressions),
+        Term::SubroutineCall(sub_call) => {
+            for argument in &mut sub_call.arguments.list {
+                eliminate_common_subexpressions(argument);
+            }
+        }
+        Term::Expression(inner) => eliminate_common_subexpressions(inner),
+        Term::UnaryOp { term, .. } => cse_units_in_term(term),
+        _ => {}
+    }
+}
+
+/// Source like text of a term that identifies equal subexpressions, `None` for terms that
+/// must not be shared
+fn key(node: &Term) -> Option<String> {
+    Some(match node {
+        Term::IntegerConstant(int) => int.to_string(),
+        Term::CharConstant(character) => char_code(*character).to_string(),
+        Term::KeywordConstant(keyword) => keyword.clone(),
+        Term::VarName(name, _) => name.clone(),
+        Term::ArrayAccess {
+            array_name,
+            indices,
+            ..
+        } => {
+            let mut buf = array_name.clone();
+            for index in indices {
+                buf.push_str(&format!("[{}]", expression_key(index)?));
+            }
+            buf
+        }
+        Term::Expression(inner) => format!("({})", expression_key(inner)?),
+        Term::UnaryOp { op, term } => format!("{}{}", op, key(term)?),
+        Term::StringConstant(_)
+        | Term::ArrayLiteral { .. }
+        | Term::SubroutineCall(_)
+        | Term::Store { .. }
+        | Term::Load(_) => return None,
+    })
+}
+
+fn expression_key(node: &Expression) -> Option<String> {
+    let mut buf = key(&node.intial_term)?;
+    for (op, node_term) in &node.afterwards {
+        buf.push_str(op);
+        buf.push_str(&key(node_term)?);
+    }
+    Some(buf)
+}
+
+/// Number of VM instructions a term compiles to, for call-free terms
+fn cost(node: &Term) -> usize {
+    match node {
+        // push arr, then per index: the index, add, pop pointer 1 and push that 0
+        Term::ArrayAccess { indices, .. } => {
+            1 + indices
+                .iter()
+                .map(|index| expression_cost(index) + 3)
+                .sum::<usize>()
+        }
+        Term::Expression(inner) => expression_cost(inner),
+        Term::UnaryOp { term, .. } => cost(term) + 1,
+        Term::Store { term, .. } => cost(term) + 2,
+        _ => 1,
+    }
+}
+
+fn expression_cost(node: &Expression) -> usize {
+    cost(&node.intial_term)
+        + node
+            .afterwards
+            .iter()
+            .map(|(_, node_term)| cost(node_term) + 1)
+            .sum::<usize>()
+}
+
+/// Later occurrences are not looked into, they are loaded as a whole
+fn count_term(node: &Term, counts: &mut HashMap<String, usize>) {
+    if let Some(node_key) = key(node) {
+        let count = counts.entry(node_key).or_default();
+        *count += 1;
+        if *count > 1 {
+            return;
+        }
+    }
+    match node {
+        Term::ArrayAccess { indices, .. } => {
+            for index in indices {
+                count_expression(index, counts);
+            }
+        }
+        Term::Expression(inner) => count_expression(inner, counts),
+        Term::UnaryOp { term, .. } | Term::Store { term, .. } => count_term(term, counts),
+        _ => {}
+    }
+}
+
+fn count_expression(node: &Expression, counts: &mut HashMap<String, usize>) {
+    count_term(&node.intial_term, counts);
+    for (_, node_term) in &node.afterwards {
+        count_term(node_term, counts);
+    }
+}
+
+struct Cse {
+    counts: HashMap<String, usize>,
+    /// Temp slot of every subexpression computed so far
+    slots: HashMap<String, u8>,
+    next_slot: u8,
+}
+
+impl Cse {
+    fn expression(&mut self, node: &mut Expression) {
+        self.term(&mut node.intial_term);
+        for (_, node_term) in &mut node.afterwards {
+            self.term(node_term);
+        }
+    }
+
+    /// Walks in evaluation order: the first occurrence stores its value, later ones load it
+    fn term(&mut self, node: &mut Term) {
+        let node_key = key(node);
+        if let Some(slot) = node_key
+            .as_ref()
+            .and_then(|node_key| self.slots.get(node_key))
+        {
+            *node = Term::Load(*slot);
+            return;
+        }
+
+        match node {
+            Term::ArrayAccess { indices, .. } => {
+                for index in indices {
+                    self.expression(index);
+                }
+            }
+            Term::Expression(inner) => self.expression(inner),
+            Term::UnaryOp { term, .. } | Term::Store { term, .. } => self.term(term),
+            _ => {}
+        }
+
+        let Some(node_key) = node_key else {
+            return;
+        };
+        // storing costs a pop and a push, every reuse saves all but one instruction
+        let reuses = self.counts[&node_key] - 1;
+        if reuses == 0 || reuses * (cost(node) - 1) <= 2 || self.next_slot > LAST_CSE_SLOT {
+            return;
+        }
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        self.slots.insert(node_key, slot);
+        let stored = std::mem::replace(node, Term::Load(slot));
+        *node = Term::Store {
+            slot,
+            term: Box::new(stored),
+        };
+    }
+}
@@ -1,9 +1,16 @@
 #![allow(non_snake_case)]
 
 use core::panic;
-use std::{collections::HashMap, vec, array};
-
-use crate::tokenizer::{TokenType::*, Tokenizer};
+use std::{collections::HashMap, vec};
+
+use crate::{
+    constants::{class_constants, constant_term, resolved_value, variable_part},
+    error::CompileError,
+    semantic::ProgramIndex,
+    tokenizer::{
+        char_code, integer_value, unescape, Dialect, Span, Token, TokenType::*, Tokenizer,
+    },
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum SubroutineType {
@@ -20,17 +27,18 @@ pub enum DeclareType {
     Var,
 }
 impl DeclareType {
-    fn to_string(&self) -> String{
-        match self{
-            DeclareType::Static => "static".to_owned(),
-            DeclareType::Field => "this".to_owned(),
-            DeclareType::Arg => "argument".to_owned(),
-            DeclareType::Var => "local".to_owned(),
+    /// VM memory segment holding variables of this kind
+    fn segment(self) -> &'static str {
+        match self {
+            DeclareType::Static => "static",
+            DeclareType::Field => "this",
+            DeclareType::Arg => "argument",
+            DeclareType::Var => "local",
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Type {
     Int,
     Char,
@@ -41,61 +49,96 @@ pub enum Type {
 
 #[derive(Debug, Clone)]
 pub struct Class {
-    name: String,
-    var_dec: Vec<ClassVarDec>,
-    sub_dec: Vec<SubroutineDec>,
+    pub name: String,
+    pub span: Span,
+    pub var_dec: Vec<ClassVarDec>,
+    pub const_dec: Vec<ConstDec>,
+    pub enum_dec: Vec<EnumDec>,
+    pub sub_dec: Vec<SubroutineDec>,
+}
+#[derive(Debug, Clone)]
+pub struct ClassVarDec {
+    pub declare_type: DeclareType,
+    pub type_type: Type,
+    pub variable_names: Vec<String>,
+    pub span: Span,
+}
+/// `const int NAME = expression;` in the extended dialect, only known inside its class
+#[derive(Debug, Clone)]
+pub struct ConstDec {
+    pub type_type: Type,
+    pub name: String,
+    pub value: Expression,
+    pub span: Span,
 }
+/// `enum Dir { UP, DOWN }` in the extended dialect, used as `Dir.UP` inside its class. The
+/// values count up from 0
 #[derive(Debug, Clone)]
-struct ClassVarDec {
-    declare_type: DeclareType,
-    type_type: Type,
-    variable_names: Vec<String>,
+pub struct EnumDec {
+    pub name: String,
+    pub values: Vec<String>,
+    pub span: Span,
 }
 #[derive(Debug, Clone)]
-struct SubroutineDec {
-    subroutine_type: SubroutineType,
-    return_type: Type,
-    name: String,
-    parameter_list: ParameterList,
-    body: SubroutineBody,
+pub struct SubroutineDec {
+    pub subroutine_type: SubroutineType,
+    pub return_type: Type,
+    pub name: String,
+    pub parameter_list: ParameterList,
+    pub body: SubroutineBody,
+    pub span: Span,
 }
 #[derive(Debug, Clone)]
-struct ParameterList {
-    list: Vec<(Type, String)>,
+pub struct ParameterList {
+    pub list: Vec<(Type, String)>,
+    pub span: Span,
 }
 #[derive(Debug, Clone)]
-struct SubroutineBody {
-    variable_declaration: Vec<VarDec>,
-    body: Vec<Statement>,
+pub struct SubroutineBody {
+    pub variable_declaration: Vec<VarDec>,
+    pub body: Vec<Statement>,
 }
 #[derive(Debug, Clone)]
-struct VarDec {
-    declare_type: DeclareType,
-    type_type: Type,
-    variable_names: Vec<String>,
+pub struct VarDec {
+    pub declare_type: DeclareType,
+    pub type_type: Type,
+    pub variable_names: Vec<String>,
+    pub span: Span,
 }
 #[derive(Debug, Clone)]
-struct SubroutineCall {
-    obj_name: Option<String>,
-    subroutine_name: String,
-    arguments: ExpressionList, // ExpressionList
+pub struct SubroutineCall {
+    pub obj_name: Option<String>,
+    pub subroutine_name: String,
+    pub arguments: ExpressionList, // ExpressionList
+    pub span: Span,
 }
 
 // Term (Op Term)*
 #[derive(Debug, Clone)]
-struct Expression {
-    intial_term: Term,
-    afterwards: Vec<(String, Term)>,
+pub struct Expression {
+    pub intial_term: Term,
+    pub afterwards: Vec<(String, Term)>,
+    pub span: Span,
 }
 #[derive(Debug, Clone)]
-enum Term {
+pub enum Term {
     IntegerConstant(u64),
+    /// The value with escapes resolved
     StringConstant(String),
+    /// `'a'` in the extended dialect
+    CharConstant(char),
     KeywordConstant(String),
-    VarName(String),
+    VarName(String, Span),
+    /// `a[i]`, or `a[i][j]` with more indices in the extended dialect
     ArrayAccess {
         array_name: String,
-        index: Box<Expression>,
+        indices: Vec<Expression>,
+        span: Span,
+    },
+    /// `[1, 2, 3]` in the extended dialect, a new Array holding the elements
+    ArrayLiteral {
+        elements: Vec<Expression>,
+        span: Span,
     },
     SubroutineCall(SubroutineCall),
     Expression(Box<Expression>),
@@ -103,632 +146,1075 @@ enum Term {
         op: String,
         term: Box<Term>,
     },
+    /// Only created by the optimizer: the value of `term`, also kept in `temp slot`
+    Store {
+        slot: u8,
+        term: Box<Term>,
+    },
+    /// Only created by the optimizer: the value kept in `temp slot` by a [`Term::Store`]
+    Load(u8),
 }
 
 #[derive(Debug, Clone)]
-struct ExpressionList {
-    list: Vec<Expression>,
+pub struct ExpressionList {
+    pub list: Vec<Expression>,
 }
 
 #[derive(Debug, Clone)]
-enum Statement {
+pub enum Statement {
     Let(Let),
     If(If),
     While(While),
     Do(Do),
     Return(Return),
+    /// Extended dialect only
+    For(Box<For>),
+    /// Extended dialect only, leaves the innermost loop
+    Break,
+    /// Extended dialect only, goes on with the next iteration of the innermost loop
+    Continue,
+    /// Extended dialect only
+    Switch(Switch),
 }
 #[derive(Debug, Clone)]
-struct Let {
-    var_name: String,
-    array_acces: Option<Expression>, // has to be a Expression
-    bind_to: Expression,             // has to be a Expression
+pub struct Let {
+    pub var_name: String,
+    /// Empty for a plain variable, more than one index only in the extended dialect
+    pub indices: Vec<Expression>,
+    pub bind_to: Expression, // has to be a Expression
+    /// `+=`, `-=`, `++` or `--` in the extended dialect, `++` and `--` bind 1
+    pub compound: Option<String>,
+    pub span: Span,
 }
 #[derive(Debug, Clone)]
-struct If {
-    condition: Expression, // has to be a Expression
-    if_true: Vec<Statement>,
-    else_part: Option<Vec<Statement>>,
+pub struct If {
+    pub condition: Expression, // has to be a Expression
+    pub if_true: Vec<Statement>,
+    pub else_part: Option<Vec<Statement>>,
 }
 #[derive(Debug, Clone)]
-struct While {
-    condition: Expression, // has to be a Expression
-    if_true: Vec<Statement>,
+pub struct While {
+    pub condition: Expression, // has to be a Expression
+    pub if_true: Vec<Statement>,
 }
+/// `for (init; condition; step) { body }`, init and step are assignments without `let`, all
+/// three parts can be left out
 #[derive(Debug, Clone)]
-struct Do {
-    call: SubroutineCall,
+pub struct For {
+    pub init: Option<Let>,
+    pub condition: Option<Expression>,
+    pub step: Option<Let>,
+    pub body: Vec<Statement>,
 }
+/// `switch (value) { case 1: ... default: ... }`, jumps to the arm with the same value and
+/// runs on through the following arms until a `break` like in C
 #[derive(Debug, Clone)]
-struct Return {
-    value: Option<Expression>, // must be Expression
+pub struct Switch {
+    pub value: Expression,
+    pub arms: Vec<SwitchArm>,
+}
+#[derive(Debug, Clone)]
+pub struct SwitchArm {
+    /// Constant expression of a `case`, `None` for `default`
+    pub label: Option<Expression>,
+    pub body: Vec<Statement>,
+}
+#[derive(Debug, Clone)]
+pub struct Do {
+    pub call: SubroutineCall,
+}
+#[derive(Debug, Clone)]
+pub struct Return {
+    pub value: Option<Expression>, // must be Expression
+    pub span: Span,
+}
+
+type ParseResult<T> = Result<T, CompileError>;
+
+/// How the operators of an expression are grouped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Precedence {
+    /// Strictly from left to right as the Jack spec requires, `1 + 2 * 3` is 9
+    #[default]
+    LeftToRight,
+    /// `* /` before `+ -` before `< >` before `=` before `&` before `|` like in C, so
+    /// `1 + 2 * 3` is 7. Implied groups end up in the AST as parenthesized expressions
+    CLike,
+}
+
+/// How C would bind an operator, higher binds tighter
+fn binding_power(op: &str) -> u8 {
+    match op {
+        "*" | "/" => 5,
+        "+" | "-" => 4,
+        "<" | ">" => 3,
+        "=" => 2,
+        "&" => 1,
+        "|" => 0,
+        _ => unreachable!(),
+    }
+}
+
+/// Operator tree of an expression, `Operand(i)` is its i-th term and `Operation(_, i, _)`
+/// its i-th operator
+#[derive(Debug, PartialEq, Eq)]
+enum Grouping {
+    Operand(usize),
+    Operation(Box<Grouping>, usize, Box<Grouping>),
 }
 
-// #[derive(Debug,Clone)]
-// pub enum TokenOrParseNode {
-//     Token(Token),
-//     ParseNode(ParseNode),
-// }
+impl Grouping {
+    fn left_to_right(op_count: usize) -> Grouping {
+        (0..op_count).fold(Grouping::Operand(0), |left, op| {
+            Grouping::Operation(Box::new(left), op, Box::new(Grouping::Operand(op + 1)))
+        })
+    }
 
-// #[derive(Debug,Clone)]
-// pub struct ParseNode {
-//     pub node_type: ParseNodeType,
-//     pub body: Vec<TokenOrParseNode>,
-// }
+    fn c_like(ops: &[String]) -> Grouping {
+        let mut next_op = 0;
+        Grouping::climb(Grouping::Operand(0), ops, &mut next_op, 0)
+    }
+
+    /// Precedence climbing: extends `left` with all operators binding at least `min_power`
+    fn climb(mut left: Grouping, ops: &[String], next_op: &mut usize, min_power: u8) -> Grouping {
+        while *next_op < ops.len() && binding_power(&ops[*next_op]) >= min_power {
+            let op = *next_op;
+            *next_op += 1;
+            let mut right = Grouping::Operand(op + 1);
+            while *next_op < ops.len() && binding_power(&ops[*next_op]) > binding_power(&ops[op]) {
+                right = Grouping::climb(right, ops, next_op, binding_power(&ops[op]) + 1);
+            }
+            left = Grouping::Operation(Box::new(left), op, Box::new(right));
+        }
+        left
+    }
+
+    /// Source like text with every group but the outermost in parentheses
+    fn render(&self, texts: &[String], ops: &[String], outermost: bool) -> String {
+        match self {
+            Grouping::Operand(term) => texts[*term].clone(),
+            Grouping::Operation(left, op, right) => {
+                let text = format!(
+                    "{} {} {}",
+                    left.render(texts, ops, false),
+                    ops[*op],
+                    right.render(texts, ops, false)
+                );
+                if outermost {
+                    text
+                } else {
+                    format!("({})", text)
+                }
+            }
+        }
+    }
+
+    /// The left spine stays one flat expression, groups on the right become parenthesized
+    /// terms, which is exactly what the code generator evaluates left to right
+    fn into_expression(self, terms: &mut [Option<Term>], ops: &[String], span: Span) -> Expression {
+        match self {
+            Grouping::Operand(term) => Expression {
+                intial_term: terms[term].take().expect("every term is used once"),
+                afterwards: vec![],
+                span,
+            },
+            Grouping::Operation(left, op, right) => {
+                let mut expression = left.into_expression(terms, ops, span);
+                let right = match *right {
+                    Grouping::Operand(term) => terms[term].take().expect("every term is used once"),
+                    group => Term::Expression(Box::new(group.into_expression(terms, ops, span))),
+                };
+                expression.afterwards.push((ops[op].clone(), right));
+                expression
+            }
+        }
+    }
+}
 
 pub struct Parser {
     tokenizer: Tokenizer,
+    dialect: Dialect,
+    precedence: Precedence,
+    /// Errors recovered from so far, parsing goes on after each of them
+    errors: Vec<CompileError>,
+    /// Found while parsing, they do not stop the compilation
+    pub warnings: Vec<CompileError>,
+    /// Number of loops around the current statement, for `break` and `continue`
+    loop_depth: usize,
+    /// Number of switch statements around the current statement, for `break`
+    switch_depth: usize,
 }
 
 impl Parser {
-    pub fn from_tokenizer(tokenizer: Tokenizer) -> Parser {
-        Self { tokenizer }
+    pub fn from_tokenizer(
+        tokenizer: Tokenizer,
+        dialect: Dialect,
+        precedence: Precedence,
+    ) -> Parser {
+        Self {
+            tokenizer,
+            dialect,
+            precedence,
+            errors: vec![],
+            warnings: vec![],
+            loop_depth: 0,
+            switch_depth: 0,
+        }
     }
-    pub fn compileClass(&mut self) -> Class {
+    /// Parses the whole class, reporting every syntax error found on the way
+    pub fn compileClass(&mut self) -> Result<Class, Vec<CompileError>> {
+        match self.compileClassInner() {
+            Ok(class_node) if self.errors.is_empty() => Ok(class_node),
+            Ok(_) => Err(std::mem::take(&mut self.errors)),
+            Err(err) => {
+                self.errors.push(err);
+                Err(std::mem::take(&mut self.errors))
+            }
+        }
+    }
+    fn compileClassInner(&mut self) -> ParseResult<Class> {
         let mut class_node = Class {
             name: String::new(),
+            span: Span::default(),
             var_dec: vec![],
+            const_dec: vec![],
+            enum_dec: vec![],
             sub_dec: vec![],
         };
 
-        self.tokenizer
-            .expect_and_string(crate::tokenizer::TokenType::Keyword, "class");
+        self.tokenizer.expect_and_string(Keyword, "class")?;
 
-        class_node.name = self
-            .tokenizer
-            .expect(crate::tokenizer::TokenType::Identifier)
-            .string_repr;
+        let name = self.tokenizer.expect(Identifier)?;
+        class_node.name = name.string_repr;
+        class_node.span = name.span;
 
-        self.tokenizer
-            .expect_and_string(crate::tokenizer::TokenType::Symbol, "{");
+        self.tokenizer.expect_and_string(Symbol, "{")?;
 
-        // classVarDec*
-        while self.tokenizer.current().t_type == Keyword
-            && (self.tokenizer.current().string_repr == "static"
-                || self.tokenizer.current().string_repr == "field")
-        {
-            class_node.var_dec.push(self.compileClassVarDec());
+        // classVarDec*, with constants and enums in between in the extended dialect
+        while self.at_class_var_start() {
+            let result = if self.tokenizer.at(Keyword, "const") {
+                self.compileConstDec()
+                    .map(|const_dec| class_node.const_dec.push(const_dec))
+            } else if self.tokenizer.at(Keyword, "enum") {
+                self.compileEnumDec()
+                    .map(|enum_dec| class_node.enum_dec.push(enum_dec))
+            } else {
+                self.compileClassVarDec()
+                    .map(|var_dec| class_node.var_dec.push(var_dec))
+            };
+            if let Err(err) = result {
+                self.errors.push(err);
+                self.skip_to_class_level();
+            }
         }
 
         // subroutineDec*
-        while self.tokenizer.current().t_type == Keyword
-            && (self.tokenizer.current().string_repr == "constructor"
-                || self.tokenizer.current().string_repr == "function"
-                || self.tokenizer.current().string_repr == "method")
-        {
-            class_node.sub_dec.push(self.compileSubroutineDec());
+        loop {
+            if self.at_subroutine_start() {
+                match self.compileSubroutineDec() {
+                    Ok(sub_dec) => class_node.sub_dec.push(sub_dec),
+                    Err(err) => {
+                        self.errors.push(err);
+                        self.skip_to_class_level();
+                    }
+                }
+            } else if self.tokenizer.is_at_end()
+                || (self.tokenizer.at(Symbol, "}") && self.tokenizer.peek().t_type == EndOfFile)
+            {
+                break;
+            } else {
+                // a stray token, most likely left over from an earlier error
+                let current = self.tokenizer.current();
+                self.errors.push(CompileError::new(
+                    format!(
+                        "expected constructor, function, method or the end of the class, found {}",
+                        current.describe()
+                    ),
+                    current.span,
+                ));
+                self.tokenizer.advance();
+                self.skip_to_class_level();
+            }
         }
 
-        self.tokenizer.expect_and_string(Symbol, "}");
+        if self.tokenizer.is_at_end() && !self.errors.is_empty() {
+            // the closing brace was most likely skipped while recovering
+            return Ok(class_node);
+        }
+        self.tokenizer.expect_and_string(Symbol, "}")?;
 
-        class_node
+        Ok(class_node)
     }
-    fn compileClassVarDec(&mut self) -> ClassVarDec {
-        let mut node_temp = ClassVarDec {
-            declare_type: DeclareType::Field, // To Be Changed
-            type_type: Type::Int,             // To Be Changed
-            variable_names: vec![],           // To Be Changed
+    /// Skips tokens up to the next class variable or subroutine declaration
+    fn skip_to_class_level(&mut self) {
+        while !(self.tokenizer.is_at_end()
+            || self.at_subroutine_start()
+            || self.at_class_var_start()
+            || (self.tokenizer.at(Symbol, "}") && self.tokenizer.peek().t_type == EndOfFile))
+        {
+            self.tokenizer.advance();
+        }
+    }
+    /// `const` and `enum` are only keywords in the extended dialect
+    fn at_class_var_start(&self) -> bool {
+        ["static", "field", "const", "enum"]
+            .iter()
+            .any(|keyword| self.tokenizer.at(Keyword, keyword))
+    }
+    fn at_subroutine_start(&self) -> bool {
+        self.tokenizer.at(Keyword, "constructor")
+            || self.tokenizer.at(Keyword, "function")
+            || self.tokenizer.at(Keyword, "method")
+    }
+    fn at_statement_start(&self) -> bool {
+        [
+            "let", "if", "while", "do", "return", "for", "break", "continue", "switch",
+        ]
+        .iter()
+        .any(|keyword| self.tokenizer.at(Keyword, keyword))
+    }
+    /// Skips tokens up to and including `symbol`
+    fn skip_past(&mut self, symbol: &str) {
+        while !self.tokenizer.is_at_end() {
+            if self.tokenizer.advance().string_repr == symbol {
+                break;
+            }
+        }
+    }
+    /// `int`, `char`, `boolean`, a class name and if allowed `void`
+    fn compileType(&mut self, allow_void: bool) -> ParseResult<Type> {
+        let type_of_dec = self.tokenizer.current();
+        let type_type = match (&type_of_dec.t_type, type_of_dec.string_repr.as_str()) {
+            (Keyword, "int") => Type::Int,
+            (Keyword, "char") => Type::Char,
+            (Keyword, "boolean") => Type::Boolean,
+            (Keyword, "void") if allow_void => Type::Void,
+            (Identifier, custom_type) => Type::ClassName(custom_type.to_string()),
+            _ => {
+                return Err(CompileError::new(
+                    format!(
+                        "expected int, char, boolean{} or a class name as type, found {}",
+                        if allow_void { ", void" } else { "" },
+                        type_of_dec.describe()
+                    ),
+                    type_of_dec.span,
+                ))
+            }
         };
-        let declare_type = match self.tokenizer.expect(Keyword).string_repr.as_str() {
+        self.tokenizer.advance();
+        Ok(type_type)
+    }
+    fn compileClassVarDec(&mut self) -> ParseResult<ClassVarDec> {
+        let start = self.tokenizer.current().span;
+        let declare_type = match self.tokenizer.expect(Keyword)?.string_repr.as_str() {
             "static" => DeclareType::Static,
             "field" => DeclareType::Field,
             _ => unreachable!(),
         };
-        node_temp.declare_type = declare_type;
+        let mut node_temp = ClassVarDec {
+            declare_type,
+            type_type: self.compileType(false)?,
+            variable_names: vec![],
+            span: start,
+        };
 
-        let type_of_dec = self.tokenizer.advance();
-        if type_of_dec.t_type == Keyword
-            && !(type_of_dec.string_repr == "int"
-                || type_of_dec.string_repr == "char"
-                || type_of_dec.string_repr == "boolean")
-        {
-            panic!("Expected int char or boolean as type if it is a keyword!");
-        } else if type_of_dec.t_type == Keyword || type_of_dec.t_type == Identifier {
-            node_temp.type_type = match type_of_dec.string_repr.as_str() {
-                "int" => Type::Int,
-                "char" => Type::Char,
-                "boolean" => Type::Boolean,
-                custom_type => Type::ClassName(custom_type.to_string()),
-            };
-        } else {
-            panic!("Expected int,char,boolean or Identifier as type in compileClassVarDec!");
-        }
         node_temp
             .variable_names
-            .push(self.tokenizer.expect(Identifier).string_repr);
+            .push(self.tokenizer.expect(Identifier)?.string_repr);
 
-        while self.tokenizer.current().t_type == Symbol
-            && self.tokenizer.current().string_repr == ","
-        {
+        while self.tokenizer.at(Symbol, ",") {
             self.tokenizer.advance();
             node_temp
                 .variable_names
-                .push(self.tokenizer.expect(Identifier).string_repr);
+                .push(self.tokenizer.expect(Identifier)?.string_repr);
         }
 
-        self.tokenizer
-            .expect_and_string(crate::tokenizer::TokenType::Symbol, ";");
+        node_temp.span = start.to(self.tokenizer.expect_and_string(Symbol, ";")?.span);
 
-        node_temp
+        Ok(node_temp)
+    }
+    fn compileConstDec(&mut self) -> ParseResult<ConstDec> {
+        let start = self.tokenizer.expect_and_string(Keyword, "const")?.span;
+        let type_type = self.compileType(false)?;
+        let name = self.tokenizer.expect(Identifier)?.string_repr;
+        self.tokenizer.expect_and_string(Symbol, "=")?;
+        let value = self.compileExpression()?;
+        let end = self.tokenizer.expect_and_string(Symbol, ";")?.span;
+
+        Ok(ConstDec {
+            type_type,
+            name,
+            value,
+            span: start.to(end),
+        })
+    }
+    fn compileEnumDec(&mut self) -> ParseResult<EnumDec> {
+        let start = self.tokenizer.expect_and_string(Keyword, "enum")?.span;
+        let name = self.tokenizer.expect(Identifier)?.string_repr;
+        self.tokenizer.expect_and_string(Symbol, "{")?;
+
+        let mut values = vec![self.tokenizer.expect(Identifier)?.string_repr];
+        while self.tokenizer.at(Symbol, ",") {
+            self.tokenizer.advance();
+            values.push(self.tokenizer.expect(Identifier)?.string_repr);
+        }
+        let end = self.tokenizer.expect_and_string(Symbol, "}")?.span;
+
+        Ok(EnumDec {
+            name,
+            values,
+            span: start.to(end),
+        })
     }
-    fn compileSubroutineDec(&mut self) -> SubroutineDec {
+    fn compileSubroutineDec(&mut self) -> ParseResult<SubroutineDec> {
         let mut node_temp = SubroutineDec {
-            subroutine_type: match self.tokenizer.expect(Keyword).string_repr.as_str() {
+            subroutine_type: match self.tokenizer.expect(Keyword)?.string_repr.as_str() {
                 "constructor" => SubroutineType::Constructor,
                 "method" => SubroutineType::Method,
                 "function" => SubroutineType::Function,
                 _ => unreachable!(),
             },
             name: String::new(),
-            parameter_list: ParameterList { list: vec![] },
+            parameter_list: ParameterList {
+                list: vec![],
+                span: Span::default(),
+            },
             body: SubroutineBody {
                 variable_declaration: vec![],
                 body: vec![],
             },
-            return_type: Type::Void,
+            return_type: self.compileType(true)?,
+            span: Span::default(),
         };
 
-        let type_of_dec = self.tokenizer.advance();
-        if type_of_dec.t_type == Keyword
-            && !(type_of_dec.string_repr == "int"
-                || type_of_dec.string_repr == "char"
-                || type_of_dec.string_repr == "boolean"
-                || type_of_dec.string_repr == "void")
-        {
-            panic!("Expected int char void or boolean as type if it is a keyword!");
-        } else if type_of_dec.t_type == Keyword || type_of_dec.t_type == Identifier {
-            node_temp.return_type = match type_of_dec.string_repr.as_str() {
-                "int" => Type::Int,
-                "char" => Type::Char,
-                "boolean" => Type::Boolean,
-                "void" => Type::Void,
-                custom_type => Type::ClassName(custom_type.to_string()),
-            };
-        } else {
-            panic!("Expected int,char,boolean,void or Identifier as type in compileSubroutineDec!");
-        }
-
-        node_temp.name = self.tokenizer.expect(Identifier).string_repr;
+        let name = self.tokenizer.expect(Identifier)?;
+        node_temp.name = name.string_repr;
+        node_temp.span = name.span;
 
-        self.tokenizer
-            .expect_and_string(crate::tokenizer::TokenType::Symbol, "(");
+        let open = self.tokenizer.expect_and_string(Symbol, "(")?;
 
-        if !(self.tokenizer.current().t_type == Symbol
-            && self.tokenizer.current().string_repr == ")")
-        {
-            node_temp.parameter_list = self.compileParameterList();
+        if !self.tokenizer.at(Symbol, ")") {
+            node_temp.parameter_list = self.compileParameterList()?;
         }
 
-        self.tokenizer
-            .expect_and_string(crate::tokenizer::TokenType::Symbol, ")");
+        let close = self.tokenizer.expect_and_string(Symbol, ")")?;
+        node_temp.parameter_list.span = open.span.to(close.span);
 
-        node_temp.body = self.compileSubroutineBody();
+        node_temp.body = self.compileSubroutineBody()?;
 
-        node_temp
+        Ok(node_temp)
     }
-    fn compileParameterList(&mut self) -> ParameterList {
-        let mut node_temp = ParameterList { list: vec![] };
-
-        let type_of_dec = self.tokenizer.advance();
-        if type_of_dec.t_type == Keyword
-            && !(type_of_dec.string_repr == "int"
-                || type_of_dec.string_repr == "char"
-                || type_of_dec.string_repr == "boolean")
-        {
-            panic!("Expected int char or boolean as type if it is a keyword!");
-        } else if type_of_dec.t_type == Keyword || type_of_dec.t_type == Identifier {
-            node_temp.list.push((
-                match type_of_dec.string_repr.as_str() {
-                    "int" => Type::Int,
-                    "char" => Type::Char,
-                    "boolean" => Type::Boolean,
-                    "void" => Type::Void,
-                    custom_type => Type::ClassName(custom_type.to_string()),
-                },
-                self.tokenizer.expect(Identifier).string_repr,
-            ));
-        } else {
-            panic!("Expected int,char,boolean or Identifier as type in compileParameterList!");
-        }
+    fn compileParameterList(&mut self) -> ParseResult<ParameterList> {
+        let mut node_temp = ParameterList {
+            list: vec![],
+            span: Span::default(),
+        };
 
-        while self.tokenizer.current().t_type == Symbol
-            && self.tokenizer.current().string_repr == ","
-        {
+        node_temp.list.push((
+            self.compileType(false)?,
+            self.tokenizer.expect(Identifier)?.string_repr,
+        ));
+
+        while self.tokenizer.at(Symbol, ",") {
             self.tokenizer.advance();
 
-            let type_of_dec = self.tokenizer.advance();
-            if type_of_dec.t_type == Keyword
-                && !(type_of_dec.string_repr == "int"
-                    || type_of_dec.string_repr == "char"
-                    || type_of_dec.string_repr == "boolean")
-            {
-                panic!("Expected int char or boolean as type if it is a keyword!");
-            } else if type_of_dec.t_type == Keyword || type_of_dec.t_type == Identifier {
-                node_temp.list.push((
-                    match type_of_dec.string_repr.as_str() {
-                        "int" => Type::Int,
-                        "char" => Type::Char,
-                        "boolean" => Type::Boolean,
-                        "void" => Type::Void,
-                        custom_type => Type::ClassName(custom_type.to_string()),
-                    },
-                    self.tokenizer.expect(Identifier).string_repr,
-                ));
-            } else {
-                panic!("Expected int,char,boolean or Identifier as type in compileParameterList!");
-            }
+            node_temp.list.push((
+                self.compileType(false)?,
+                self.tokenizer.expect(Identifier)?.string_repr,
+            ));
         }
 
-        node_temp
+        Ok(node_temp)
     }
-    fn compileSubroutineBody(&mut self) -> SubroutineBody {
+    fn compileSubroutineBody(&mut self) -> ParseResult<SubroutineBody> {
         let mut node_temp = SubroutineBody {
             variable_declaration: vec![],
             body: vec![],
         };
 
-        self.tokenizer
-            .expect_and_string(crate::tokenizer::TokenType::Symbol, "{");
+        self.tokenizer.expect_and_string(Symbol, "{")?;
 
         // varDec*
-        while self.tokenizer.current().t_type == Keyword
-            && self.tokenizer.current().string_repr == "var"
-        {
-            node_temp.variable_declaration.push(self.compileVarDec());
+        while self.tokenizer.at(Keyword, "var") {
+            match self.compileVarDec() {
+                Ok(var_dec) => node_temp.variable_declaration.push(var_dec),
+                Err(err) => {
+                    self.errors.push(err);
+                    self.skip_past(";");
+                }
+            }
         }
 
         // statements
         node_temp.body = self.compileStatements();
 
-        self.tokenizer
-            .expect_and_string(crate::tokenizer::TokenType::Symbol, "}");
-        node_temp
+        self.tokenizer.expect_and_string(Symbol, "}")?;
+        Ok(node_temp)
     }
-    fn compileVarDec(&mut self) -> VarDec {
+    fn compileVarDec(&mut self) -> ParseResult<VarDec> {
+        let start = self.tokenizer.expect(Keyword)?.span;
+
         let mut node_temp = VarDec {
             declare_type: DeclareType::Var,
-            type_type: Type::Int,
+            type_type: self.compileType(false)?,
             variable_names: vec![],
+            span: start,
         };
-        self.tokenizer.expect(Keyword);
-
-        let type_of_dec = self.tokenizer.advance();
-        if type_of_dec.t_type == Keyword
-            && !(type_of_dec.string_repr == "int"
-                || type_of_dec.string_repr == "char"
-                || type_of_dec.string_repr == "boolean")
-        {
-            panic!("Expected int char or boolean as type if it is a keyword!");
-        } else if type_of_dec.t_type == Keyword || type_of_dec.t_type == Identifier {
-            node_temp.type_type = match type_of_dec.string_repr.as_str() {
-                "int" => Type::Int,
-                "char" => Type::Char,
-                "boolean" => Type::Boolean,
-                "void" => Type::Void,
-                custom_type => Type::ClassName(custom_type.to_string()),
-            };
-        } else {
-            panic!("Expected int,char,boolean or Identifier as type in compileClassVarDec!");
-        }
         node_temp
             .variable_names
-            .push(self.tokenizer.expect(Identifier).string_repr);
+            .push(self.tokenizer.expect(Identifier)?.string_repr);
 
-        while self.tokenizer.current().t_type == Symbol
-            && self.tokenizer.current().string_repr == ","
-        {
+        while self.tokenizer.at(Symbol, ",") {
             self.tokenizer.advance();
             node_temp
                 .variable_names
-                .push(self.tokenizer.expect(Identifier).string_repr);
+                .push(self.tokenizer.expect(Identifier)?.string_repr);
         }
 
-        self.tokenizer
-            .expect_and_string(crate::tokenizer::TokenType::Symbol, ";");
+        node_temp.span = start.to(self.tokenizer.expect_and_string(Symbol, ";")?.span);
 
-        node_temp
+        Ok(node_temp)
     }
+    /// statement*, a broken statement is reported and skipped up to the next `;`, `}`
+    /// or statement keyword
     fn compileStatements(&mut self) -> Vec<Statement> {
         let mut temp = vec![];
-        // statement*
         // either let,if,while,do,return
-        while self.tokenizer.current().t_type == Keyword
-            && (self.tokenizer.current().string_repr == "let"
-                || self.tokenizer.current().string_repr == "if"
-                || self.tokenizer.current().string_repr == "while"
-                || self.tokenizer.current().string_repr == "do"
-                || self.tokenizer.current().string_repr == "return")
-        {
-            match self.tokenizer.current().string_repr.as_str() {
-                "let" => {
-                    temp.push(self.compileLet());
-                }
-                "if" => {
-                    temp.push(self.compileIf());
-                }
-                "while" => {
-                    temp.push(self.compileWhile());
-                }
-                "do" => {
-                    temp.push(self.compileDo());
-                }
-                "return" => {
-                    temp.push(self.compileReturn());
-                }
+        while self.at_statement_start() {
+            let statement = match self.tokenizer.current().string_repr.as_str() {
+                "let" => self.compileLet(),
+                "if" => self.compileIf(),
+                "while" => self.compileWhile(),
+                "do" => self.compileDo(),
+                "return" => self.compileReturn(),
+                "for" => self.compileFor(),
+                "break" | "continue" => self.compileLoopJump(),
+                "switch" => self.compileSwitch(),
                 _ => unreachable!(),
+            };
+            match statement {
+                Ok(statement) => temp.push(statement),
+                Err(err) => {
+                    self.errors.push(err);
+                    while !(self.tokenizer.is_at_end()
+                        || self.tokenizer.at(Symbol, "}")
+                        || self.at_statement_start())
+                    {
+                        if self.tokenizer.advance().string_repr == ";" {
+                            break;
+                        }
+                    }
+                }
             }
         }
 
         temp
     }
-    fn compileLet(&mut self) -> Statement {
-        let mut node_temp = Let {
-            var_name: String::new(),
-            array_acces: None,
-            bind_to: Expression {
-                intial_term: Term::IntegerConstant(7),
-                afterwards: vec![],
-            },
-        };
-
-        self.tokenizer.expect(Keyword);
+    fn compileLet(&mut self) -> ParseResult<Statement> {
+        self.tokenizer.expect(Keyword)?;
 
-        node_temp.var_name = self.tokenizer.expect(Identifier).string_repr;
+        let let_statement = self.compileAssignment()?;
 
-        if self.tokenizer.current().t_type == Symbol && self.tokenizer.current().string_repr == "["
-        {
+        self.tokenizer.expect_and_string(Symbol, ";")?;
+        Ok(Statement::Let(let_statement))
+    }
+    /// `varName ([expression])? = expression`, a let statement without `let` and `;`. The
+    /// extended dialect also assigns to `obj.field`
+    fn compileAssignment(&mut self) -> ParseResult<Let> {
+        let mut var_name = self.tokenizer.expect(Identifier)?;
+        if self.dialect == Dialect::Extended && self.tokenizer.at(Symbol, ".") {
             self.tokenizer.advance();
-            node_temp.array_acces = Some(self.compileExpression());
-
-            self.tokenizer.expect_and_string(Symbol, "]");
+            let field = self.tokenizer.expect(Identifier)?;
+            var_name.string_repr = format!("{}.{}", var_name.string_repr, field.string_repr);
+            var_name.span = var_name.span.to(field.span);
         }
 
-        self.tokenizer.expect_and_string(Symbol, "=");
+        let mut indices = vec![];
+        if self.tokenizer.at(Symbol, "[") {
+            indices = self.compileIndices()?.0;
+        }
 
-        node_temp.bind_to = self.compileExpression();
+        let mut compound = None;
+        let bind_to;
+        if let Some(op) = self.compound_assignment() {
+            // `++` and `--` are `+= 1` and `-= 1`
+            let second = self.tokenizer.advance();
+            compound = Some(format!("{}{}", op, second.string_repr));
+            bind_to = match second.string_repr.as_str() {
+                "=" => self.compileExpression()?,
+                _ => Expression {
+                    intial_term: Term::IntegerConstant(1),
+                    afterwards: vec![],
+                    span: second.span,
+                },
+            };
+        } else {
+            self.tokenizer.expect_and_string(Symbol, "=")?;
+            bind_to = self.compileExpression()?;
+        }
 
-        self.tokenizer.expect_and_string(Symbol, ";");
-        Statement::Let(node_temp)
+        Ok(Let {
+            var_name: var_name.string_repr,
+            indices,
+            bind_to,
+            compound,
+            span: var_name.span,
+        })
     }
-    fn compileIf(&mut self) -> Statement {
-        let mut node_temp = If {
-            condition: Expression {
-                intial_term: Term::IntegerConstant(7),
-                afterwards: vec![],
-            },
-            if_true: vec![],
-            else_part: None,
-        };
-
-        self.tokenizer.expect(Keyword);
+    /// `[expression]`, repeated as in `[i][j]` in the extended dialect, and the span of the
+    /// last `]`
+    fn compileIndices(&mut self) -> ParseResult<(Vec<Expression>, Span)> {
+        let mut indices = vec![];
+        loop {
+            self.tokenizer.expect_and_string(Symbol, "[")?;
+            indices.push(self.compileExpression()?);
+            let close = self.tokenizer.expect_and_string(Symbol, "]")?;
+            if self.dialect != Dialect::Extended || !self.tokenizer.at(Symbol, "[") {
+                return Ok((indices, close.span));
+            }
+        }
+    }
+    /// Consumes the first symbol of `+=`, `-=`, `++` or `--` and gives back its operator.
+    /// Both symbols have to touch, `x + = 1` stays an error
+    fn compound_assignment(&mut self) -> Option<String> {
+        if self.dialect != Dialect::Extended {
+            return None;
+        }
+        let (first, second) = (self.tokenizer.current(), self.tokenizer.peek());
+        let touching = first.span.line == second.span.line && first.span.col + 1 == second.span.col;
+        let op = first.string_repr;
+        let is_compound = first.t_type == Symbol
+            && second.t_type == Symbol
+            && (op == "+" || op == "-")
+            && (second.string_repr == "=" || second.string_repr == op);
+        if !(is_compound && touching) {
+            return None;
+        }
+        self.tokenizer.advance();
+        Some(op)
+    }
+    fn compileIf(&mut self) -> ParseResult<Statement> {
+        self.tokenizer.expect(Keyword)?;
 
-        self.tokenizer.expect_and_string(Symbol, "(");
+        self.tokenizer.expect_and_string(Symbol, "(")?;
 
-        node_temp.condition = self.compileExpression();
+        let condition = self.compileExpression()?;
 
-        self.tokenizer.expect_and_string(Symbol, ")");
-        self.tokenizer.expect_and_string(Symbol, "{");
+        self.tokenizer.expect_and_string(Symbol, ")")?;
+        self.tokenizer.expect_and_string(Symbol, "{")?;
 
-        node_temp.if_true = self.compileStatements();
+        let if_true = self.compileStatements();
 
-        self.tokenizer.expect_and_string(Symbol, "}");
+        self.tokenizer.expect_and_string(Symbol, "}")?;
 
-        if self.tokenizer.current().t_type == Keyword
-            && self.tokenizer.current().string_repr == "else"
-        {
+        let mut else_part = None;
+        if self.tokenizer.at(Keyword, "else") {
             self.tokenizer.advance();
 
-            self.tokenizer.expect_and_string(Symbol, "{");
+            // `else if` is an else branch holding only the next if statement
+            if self.dialect == Dialect::Extended && self.tokenizer.at(Keyword, "if") {
+                else_part = Some(vec![self.compileIf()?]);
+                return Ok(Statement::If(If {
+                    condition,
+                    if_true,
+                    else_part,
+                }));
+            }
+
+            self.tokenizer.expect_and_string(Symbol, "{")?;
 
-            node_temp.else_part = Some(self.compileStatements());
+            else_part = Some(self.compileStatements());
 
-            self.tokenizer.expect_and_string(Symbol, "}");
+            self.tokenizer.expect_and_string(Symbol, "}")?;
         }
 
-        Statement::If(node_temp)
+        Ok(Statement::If(If {
+            condition,
+            if_true,
+            else_part,
+        }))
     }
-    fn compileWhile(&mut self) -> Statement {
-        let mut node_temp = While {
-            condition: Expression {
-                intial_term: Term::IntegerConstant(7),
-                afterwards: vec![],
-            },
-            if_true: vec![],
-        };
-        self.tokenizer.expect(Keyword);
+    fn compileWhile(&mut self) -> ParseResult<Statement> {
+        self.tokenizer.expect(Keyword)?;
 
-        self.tokenizer.expect_and_string(Symbol, "(");
+        self.tokenizer.expect_and_string(Symbol, "(")?;
 
-        node_temp.condition = self.compileExpression();
+        let condition = self.compileExpression()?;
 
-        self.tokenizer.expect_and_string(Symbol, ")");
-        self.tokenizer.expect_and_string(Symbol, "{");
+        self.tokenizer.expect_and_string(Symbol, ")")?;
+        self.tokenizer.expect_and_string(Symbol, "{")?;
 
-        node_temp.if_true = self.compileStatements();
+        let if_true = self.compileLoopBody();
 
-        self.tokenizer.expect_and_string(Symbol, "}");
+        self.tokenizer.expect_and_string(Symbol, "}")?;
 
-        Statement::While(node_temp)
+        Ok(Statement::While(While { condition, if_true }))
     }
-    fn compileDo(&mut self) -> Statement {
-        let mut node_temp = Do {
-            call: SubroutineCall {
-                obj_name: None,
-                subroutine_name: String::new(),
-                arguments: ExpressionList { list: vec![] },
-            },
-        };
-        self.tokenizer.expect(Keyword);
+    fn compileFor(&mut self) -> ParseResult<Statement> {
+        self.tokenizer.expect(Keyword)?;
 
-        node_temp.call.subroutine_name = self.tokenizer.expect(Identifier).string_repr;
-        if self.tokenizer.current().t_type == Symbol && self.tokenizer.current().string_repr == "."
-        {
-            self.tokenizer.expect(Symbol);
+        self.tokenizer.expect_and_string(Symbol, "(")?;
+
+        let mut init = None;
+        if !self.tokenizer.at(Symbol, ";") {
+            init = Some(self.compileAssignment()?);
+        }
+        self.tokenizer.expect_and_string(Symbol, ";")?;
+
+        let mut condition = None;
+        if !self.tokenizer.at(Symbol, ";") {
+            condition = Some(self.compileExpression()?);
+        }
+        self.tokenizer.expect_and_string(Symbol, ";")?;
+
+        let mut step = None;
+        if !self.tokenizer.at(Symbol, ")") {
+            step = Some(self.compileAssignment()?);
+        }
 
-            node_temp.call.obj_name = Some(node_temp.call.subroutine_name);
-            node_temp.call.subroutine_name = self.tokenizer.expect(Identifier).string_repr;
+        self.tokenizer.expect_and_string(Symbol, ")")?;
+        self.tokenizer.expect_and_string(Symbol, "{")?;
+
+        let body = self.compileLoopBody();
+
+        self.tokenizer.expect_and_string(Symbol, "}")?;
+
+        Ok(Statement::For(Box::new(For {
+            init,
+            condition,
+            step,
+            body,
+        })))
+    }
+    fn compileLoopBody(&mut self) -> Vec<Statement> {
+        self.loop_depth += 1;
+        let body = self.compileStatements();
+        self.loop_depth -= 1;
+        body
+    }
+    /// `break;` or `continue;`
+    fn compileLoopJump(&mut self) -> ParseResult<Statement> {
+        let keyword = self.tokenizer.expect(Keyword)?;
+        self.tokenizer.expect_and_string(Symbol, ";")?;
+
+        if keyword.string_repr == "break" && self.loop_depth + self.switch_depth == 0 {
+            return Err(CompileError::new(
+                "break outside of a loop or switch",
+                keyword.span,
+            ));
         }
+        if keyword.string_repr == "continue" && self.loop_depth == 0 {
+            return Err(CompileError::new(
+                "continue outside of a loop",
+                keyword.span,
+            ));
+        }
+        Ok(match keyword.string_repr.as_str() {
+            "break" => Statement::Break,
+            _ => Statement::Continue,
+        })
+    }
+    fn compileSwitch(&mut self) -> ParseResult<Statement> {
+        self.tokenizer.expect(Keyword)?;
 
-        self.tokenizer.expect_and_string(Symbol, "(");
-        node_temp.call.arguments = self.compileExpressionList();
+        self.tokenizer.expect_and_string(Symbol, "(")?;
+        let value = self.compileExpression()?;
+        self.tokenizer.expect_and_string(Symbol, ")")?;
+        self.tokenizer.expect_and_string(Symbol, "{")?;
 
-        self.tokenizer.expect_and_string(Symbol, ")");
+        let mut arms = vec![];
+        self.switch_depth += 1;
+        let result = self.compileSwitchArms(&mut arms);
+        self.switch_depth -= 1;
+        result?;
 
-        self.tokenizer.expect_and_string(Symbol, ";");
+        self.tokenizer.expect_and_string(Symbol, "}")?;
 
-        Statement::Do(node_temp)
+        Ok(Statement::Switch(Switch { value, arms }))
     }
-    fn compileReturn(&mut self) -> Statement {
-        let mut node_temp = Return { value: None };
-        self.tokenizer.expect(Keyword);
+    fn compileSwitchArms(&mut self, arms: &mut Vec<SwitchArm>) -> ParseResult<()> {
+        while self.tokenizer.at(Keyword, "case") || self.tokenizer.at(Keyword, "default") {
+            let keyword = self.tokenizer.advance();
+            let label = match keyword.string_repr.as_str() {
+                "case" => Some(self.compileExpression()?),
+                _ if arms.iter().any(|arm: &SwitchArm| arm.label.is_none()) => {
+                    return Err(CompileError::new(
+                        "a switch can only have one default",
+                        keyword.span,
+                    ))
+                }
+                _ => None,
+            };
+            self.tokenizer.expect_and_string(Symbol, ":")?;
 
-        if !(self.tokenizer.current().t_type == Symbol
-            && self.tokenizer.current().string_repr == ";")
-        {
-            node_temp.value = Some(self.compileExpression());
+            arms.push(SwitchArm {
+                label,
+                body: self.compileStatements(),
+            });
         }
+        Ok(())
+    }
+    fn compileDo(&mut self) -> ParseResult<Statement> {
+        self.tokenizer.expect(Keyword)?;
+
+        let name = self.tokenizer.expect(Identifier)?;
+        let call = self.compileSubroutineCall(name)?;
+
+        self.tokenizer.expect_and_string(Symbol, ";")?;
 
-        self.tokenizer.expect_and_string(Symbol, ";");
-        Statement::Return(node_temp)
+        Ok(Statement::Do(Do { call }))
     }
-    fn compileExpression(&mut self) -> Expression {
-        let mut node_temp = Expression {
-            intial_term: self.compileTerm(),
-            afterwards: vec![],
+    fn compileReturn(&mut self) -> ParseResult<Statement> {
+        let mut node_temp = Return {
+            value: None,
+            span: self.tokenizer.expect(Keyword)?.span,
         };
 
-        while self.tokenizer.current().t_type == Symbol
-            && (self.tokenizer.current().string_repr == "+"
-                || self.tokenizer.current().string_repr == "-"
-                || self.tokenizer.current().string_repr == "*"
-                || self.tokenizer.current().string_repr == "/"
-                || self.tokenizer.current().string_repr == "&"
-                || self.tokenizer.current().string_repr == "|"
-                || self.tokenizer.current().string_repr == "<"
-                || self.tokenizer.current().string_repr == ">"
-                || self.tokenizer.current().string_repr == "=")
+        if !self.tokenizer.at(Symbol, ";") {
+            node_temp.value = Some(self.compileExpression()?);
+        }
+
+        node_temp.span = node_temp
+            .span
+            .to(self.tokenizer.expect_and_string(Symbol, ";")?.span);
+        Ok(Statement::Return(node_temp))
+    }
+    fn compileExpression(&mut self) -> ParseResult<Expression> {
+        let start = self.tokenizer.current().span;
+        // where every term starts and ends, to show the expression in warnings
+        let mut term_positions = vec![];
+        let mut terms = vec![];
+        let mut ops = vec![];
+
+        let term_start = self.tokenizer.position();
+        terms.push(Some(self.compileTerm()?));
+        term_positions.push((term_start, self.tokenizer.position()));
+        while ["+", "-", "*", "/", "&", "|", "<", ">", "="]
+            .iter()
+            .any(|op| self.tokenizer.at(Symbol, op))
         {
-            node_temp
-                .afterwards
-                .push((self.tokenizer.advance().string_repr, self.compileTerm()));
+            ops.push(self.tokenizer.advance().string_repr);
+            let term_start = self.tokenizer.position();
+            terms.push(Some(self.compileTerm()?));
+            term_positions.push((term_start, self.tokenizer.position()));
+        }
+        let span = start.to(self.tokenizer.previous_span());
+
+        let left_to_right = Grouping::left_to_right(ops.len());
+        let c_like = Grouping::c_like(&ops);
+        if left_to_right != c_like {
+            let texts: Vec<String> = term_positions
+                .iter()
+                .map(|(start, end)| self.tokenizer.text(*start, *end))
+                .collect();
+            let mut source = texts[0].clone();
+            for (op, text) in ops.iter().zip(&texts[1..]) {
+                source.push_str(&format!(" {} {}", op, text));
+            }
+            self.warnings.push(CompileError::warning(
+                format!(
+                    "`{}` is `{}` in standard Jack but `{}` with operator precedence",
+                    source,
+                    left_to_right.render(&texts, &ops, true),
+                    c_like.render(&texts, &ops, true),
+                ),
+                span,
+            ));
         }
 
-        node_temp
+        let grouping = match self.precedence {
+            Precedence::LeftToRight => left_to_right,
+            Precedence::CLike => c_like,
+        };
+        Ok(grouping.into_expression(&mut terms, &ops, span))
     }
-    fn compileTerm(&mut self) -> Term {
-        let mut node_temp = Term::IntegerConstant(7);
-        match self.tokenizer.current().t_type {
+    /// Takes a string or char constant and resolves its escapes, standard Jack has none
+    fn decoded(&mut self) -> String {
+        let written = self.tokenizer.advance().string_repr;
+        match self.dialect {
+            Dialect::Standard => written,
+            Dialect::Extended => unescape(&written).expect("the lexer checked the escapes"),
+        }
+    }
+    fn compileTerm(&mut self) -> ParseResult<Term> {
+        let current = self.tokenizer.current();
+        let node_temp = match current.t_type {
             Keyword => {
                 // either true false null or this
-                if self.tokenizer.current().string_repr == "true"
-                    || self.tokenizer.current().string_repr == "false"
-                    || self.tokenizer.current().string_repr == "null"
-                    || self.tokenizer.current().string_repr == "this"
-                {
-                    node_temp = Term::KeywordConstant(self.tokenizer.advance().string_repr);
+                if ["true", "false", "null", "this"].contains(&current.string_repr.as_str()) {
+                    Term::KeywordConstant(self.tokenizer.advance().string_repr)
                 } else {
-                    panic!("keyword in Term other than true false or null or this arent possible! found: {:?}",self.tokenizer.current());
+                    return Err(CompileError::new(
+                        format!(
+                            "expected an expression, found {} (only true, false, null and this are keyword constants)",
+                            current.describe()
+                        ),
+                        current.span,
+                    ));
                 }
             }
             Symbol => {
                 // either expression in paratheses or unaryOp
-                if self.tokenizer.current().string_repr == "-"
-                    || self.tokenizer.current().string_repr == "~"
+                let next = self.tokenizer.peek();
+                if current.string_repr == "-"
+                    && next.t_type == IntegerConstant
+                    && integer_value(&next.string_repr) == Some(32768)
                 {
-                    node_temp = Term::UnaryOp {
+                    // -32768 is the only place 32768 can be written
+                    self.tokenizer.advance();
+                    self.tokenizer.advance();
+                    Term::UnaryOp {
+                        op: "-".to_string(),
+                        term: Box::new(Term::IntegerConstant(32768)),
+                    }
+                } else if current.string_repr == "-" || current.string_repr == "~" {
+                    Term::UnaryOp {
                         op: self.tokenizer.advance().string_repr,
-                        term: Box::new(self.compileTerm()),
-                    };
-                } else if self.tokenizer.current().string_repr == "(" {
+                        term: Box::new(self.compileTerm()?),
+                    }
+                } else if current.string_repr == "(" {
                     self.tokenizer.advance();
-                    node_temp = Term::Expression(Box::new(self.compileExpression()));
+                    let expression = self.compileExpression()?;
 
-                    self.tokenizer.expect_and_string(Symbol, ")");
+                    self.tokenizer.expect_and_string(Symbol, ")")?;
+                    Term::Expression(Box::new(expression))
+                } else if current.string_repr == "[" && self.dialect == Dialect::Extended {
+                    self.compileArrayLiteral()?
                 } else {
-                    panic!(
-                        "Symbol in Term other than ~,- or (  arent possible! found: {:?}",
-                        self.tokenizer.current()
-                    );
+                    return Err(CompileError::new(
+                        format!("expected an expression, found {}", current.describe()),
+                        current.span,
+                    ));
                 }
             }
             Identifier => {
                 let token = self.tokenizer.advance();
-                if self.tokenizer.current().t_type == Symbol
-                    && self.tokenizer.current().string_repr == "["
-                {
-                    self.tokenizer.expect_and_string(Symbol, "[");
-
-                    node_temp = Term::ArrayAccess {
+                if self.tokenizer.at(Symbol, "[") {
+                    let (indices, close) = self.compileIndices()?;
+                    Term::ArrayAccess {
                         array_name: token.string_repr,
-                        index: Box::new(self.compileExpression()),
-                    };
-
-                    self.tokenizer.expect_and_string(Symbol, "]");
-                } else if self.tokenizer.current().t_type == Symbol
-                    && self.tokenizer.current().string_repr == "("
-                {
-                    self.tokenizer.expect_and_string(Symbol, "(");
-                    node_temp = Term::SubroutineCall(SubroutineCall {
-                        obj_name: None,
-                        subroutine_name: token.string_repr,
-                        arguments: self.compileExpressionList(),
-                    });
-
-                    self.tokenizer.expect_and_string(Symbol, ")");
-                } else if self.tokenizer.current().t_type == Symbol
-                    && self.tokenizer.current().string_repr == "."
+                        indices,
+                        span: token.span.to(close),
+                    }
+                } else if self.dialect == Dialect::Extended
+                    && self.tokenizer.at(Symbol, ".")
+                    && self.tokenizer.peek().t_type == Identifier
+                    && self.tokenizer.peek_at(2).string_repr != "("
                 {
-                    self.tokenizer.expect(Symbol);
-                    let mut temp = SubroutineCall {
-                        obj_name: Some(token.string_repr),
-                        subroutine_name: self.tokenizer.expect(Identifier).string_repr,
-                        arguments: ExpressionList { list: vec![] },
-                    };
-                    self.tokenizer.expect_and_string(Symbol, "(");
-
-                    temp.arguments = self.compileExpressionList();
-
-                    self.tokenizer.expect_and_string(Symbol, ")");
-
-                    node_temp = Term::SubroutineCall(temp);
+                    // `Dir.UP`, a value of an enum, or `p.x`, a field of another object
+                    self.tokenizer.advance();
+                    let member = self.tokenizer.advance();
+                    let name = format!("{}.{}", token.string_repr, member.string_repr);
+                    if self.tokenizer.at(Symbol, "[") {
+                        let (indices, close) = self.compileIndices()?;
+                        Term::ArrayAccess {
+                            array_name: name,
+                            indices,
+                            span: token.span.to(close),
+                        }
+                    } else {
+                        Term::VarName(name, token.span.to(member.span))
+                    }
+                } else if self.tokenizer.at(Symbol, "(") || self.tokenizer.at(Symbol, ".") {
+                    Term::SubroutineCall(self.compileSubroutineCall(token)?)
                 } else {
-                    node_temp = Term::VarName(token.string_repr);
+                    Term::VarName(token.string_repr, token.span)
                 }
             }
             IntegerConstant => {
-                node_temp =
-                    Term::IntegerConstant(self.tokenizer.advance().string_repr.parse().unwrap());
+                let written = self.tokenizer.advance().string_repr;
+                let int = integer_value(&written).expect("the lexer checked the range");
+                if int > 32767 {
+                    return Err(CompileError::new(
+                        format!(
+                            "integer constant {} is bigger than 32767, only -{} can be written",
+                            written, written
+                        ),
+                        current.span,
+                    ));
+                }
+                Term::IntegerConstant(int)
+            }
+            StringConstant => Term::StringConstant(self.decoded()),
+            CharConstant => {
+                let value = self.decoded();
+                Term::CharConstant(value.chars().next().expect("the lexer checked the length"))
             }
-            StringConstant => {
-                node_temp =
-                    Term::StringConstant(self.tokenizer.advance().string_repr.parse().unwrap());
+            EndOfFile => {
+                return Err(CompileError::new(
+                    "expected an expression, found end of file",
+                    current.span,
+                ))
             }
+        };
+
+        Ok(node_temp)
+    }
+    /// `[expression (, expression)*]`, Array.new(0) is an error in the OS so there is at
+    /// least one element
+    fn compileArrayLiteral(&mut self) -> ParseResult<Term> {
+        let open = self.tokenizer.expect_and_string(Symbol, "[")?;
+        if self.tokenizer.at(Symbol, "]") {
+            return Err(CompileError::new(
+                "an array literal needs at least one element",
+                open.span.to(self.tokenizer.current().span),
+            ));
         }
+        let mut elements = vec![self.compileExpression()?];
+        while self.tokenizer.at(Symbol, ",") {
+            self.tokenizer.advance();
+            elements.push(self.compileExpression()?);
+        }
+        let close = self.tokenizer.expect_and_string(Symbol, "]")?;
 
-        node_temp
+        Ok(Term::ArrayLiteral {
+            elements,
+            span: open.span.to(close.span),
+        })
+    }
+    /// `name(args)` or `name.name(args)`, the first name is already consumed
+    fn compileSubroutineCall(&mut self, name: Token) -> ParseResult<SubroutineCall> {
+        let mut call = SubroutineCall {
+            obj_name: None,
+            subroutine_name: name.string_repr,
+            arguments: ExpressionList { list: vec![] },
+            span: name.span,
+        };
+        if self.tokenizer.at(Symbol, ".") {
+            self.tokenizer.advance();
+
+            call.obj_name = Some(call.subroutine_name);
+            call.subroutine_name = self.tokenizer.expect(Identifier)?.string_repr;
+        }
+
+        self.tokenizer.expect_and_string(Symbol, "(")?;
+        call.arguments = self.compileExpressionList()?;
+
+        call.span = name
+            .span
+            .to(self.tokenizer.expect_and_string(Symbol, ")")?.span);
+
+        Ok(call)
     }
-    fn compileExpressionList(&mut self) -> ExpressionList {
+    fn compileExpressionList(&mut self) -> ParseResult<ExpressionList> {
         let mut node_temp = ExpressionList { list: vec![] };
 
         // only if there is a expression list
-        if self.tokenizer.current().string_repr != ")" {
-            node_temp.list.push(self.compileExpression());
+        if !self.tokenizer.at(Symbol, ")") {
+            node_temp.list.push(self.compileExpression()?);
 
-            while self.tokenizer.current().t_type == Symbol
-                && self.tokenizer.current().string_repr == ","
-            {
+            while self.tokenizer.at(Symbol, ",") {
                 self.tokenizer.advance();
-                node_temp.list.push(self.compileExpression());
+                node_temp.list.push(self.compileExpression()?);
             }
         }
 
-        node_temp
+        Ok(node_temp)
     }
 }
 
 #[derive(Debug, Clone)]
 struct Variable {
-    name: String,
     v_type: Type,
     kind: DeclareType,
     number: u64,
@@ -737,19 +1223,28 @@ struct Variable {
 #[derive(Debug, Clone)]
 struct Scope {
     table: HashMap<String, Variable>,
+    /// Constants and enum values, they take no slot in any segment
+    constants: HashMap<String, i16>,
     static_counter: u64,
     field_counter: u64,
     arg_counter: u64,
     var_counter: u64,
 }
-impl Scope{
-    fn insert(&mut self,var_name: String,v_type: Type,declare_type: DeclareType){
-        self.table.insert(var_name.clone(), Variable { name: var_name, v_type, kind: declare_type, number: match declare_type {
-            DeclareType::Static => self.static_counter,
-            DeclareType::Field => self.field_counter,
-            DeclareType::Arg => self.arg_counter,
-            DeclareType::Var => self.var_counter,
-        } });
+impl Scope {
+    fn insert(&mut self, var_name: String, v_type: Type, declare_type: DeclareType) {
+        self.table.insert(
+            var_name.clone(),
+            Variable {
+                v_type,
+                kind: declare_type,
+                number: match declare_type {
+                    DeclareType::Static => self.static_counter,
+                    DeclareType::Field => self.field_counter,
+                    DeclareType::Arg => self.arg_counter,
+                    DeclareType::Var => self.var_counter,
+                },
+            },
+        );
         let num: &mut u64 = match declare_type {
             DeclareType::Static => &mut self.static_counter,
             DeclareType::Field => &mut self.field_counter,
@@ -760,17 +1255,24 @@ impl Scope{
     }
 }
 
-pub struct CodeGenerator {
+/// Generates VM code for one class, looking up the other classes of the program in `index`
+pub struct CodeGenerator<'a> {
+    index: &'a ProgramIndex,
     main_class_name: String,
     class_table: Scope,
     subroutine_table: Scope,
     label_counter: u64,
+    /// Where `continue` and `break` jump to for every loop and switch around the current
+    /// statement, `continue` in a switch goes on with the loop around it
+    loop_labels: Vec<(Option<String>, String)>,
 }
-impl CodeGenerator {
-    pub fn new() -> Self {
+impl<'a> CodeGenerator<'a> {
+    pub fn new(index: &'a ProgramIndex) -> Self {
         CodeGenerator {
+            index,
             class_table: Scope {
                 table: HashMap::new(),
+                constants: HashMap::new(),
                 static_counter: 0,
                 field_counter: 0,
                 var_counter: 0,
@@ -778,64 +1280,79 @@ impl CodeGenerator {
             },
             subroutine_table: Scope {
                 table: HashMap::new(),
+                constants: HashMap::new(),
                 static_counter: 0,
                 field_counter: 0,
                 var_counter: 0,
                 arg_counter: 0,
             },
             label_counter: 0,
+            loop_labels: vec![],
             main_class_name: String::new(),
         }
     }
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_vm_code(&mut self, node: Class) -> String {
         let mut buf: Vec<String> = vec![];
 
         self.main_class_name = node.name.clone();
-
-        for class_level_var in node.var_dec.clone(){
-            for name in class_level_var.variable_names{
-                self.class_table.insert(name,class_level_var.type_type.clone(),class_level_var.declare_type);
+        self.class_table.constants = class_constants(&node);
+
+        for class_level_var in node.var_dec.clone() {
+            for name in class_level_var.variable_names {
+                self.class_table.insert(
+                    name,
+                    class_level_var.type_type.clone(),
+                    class_level_var.declare_type,
+                );
             }
         }
-        for sub in node.sub_dec{
+        for sub in node.sub_dec {
             if let SubroutineType::Method = sub.subroutine_type {
                 self.subroutine_table.arg_counter += 1;
             }
 
-            for arg in sub.parameter_list.list{
+            for arg in sub.parameter_list.list {
                 self.subroutine_table.insert(arg.1, arg.0, DeclareType::Arg);
             }
-            for var_dec in sub.body.variable_declaration{
-                for name in var_dec.variable_names{
-                    self.subroutine_table.insert(name, var_dec.type_type.clone(), var_dec.declare_type);
+            for var_dec in sub.body.variable_declaration {
+                for name in var_dec.variable_names {
+                    self.subroutine_table.insert(
+                        name,
+                        var_dec.type_type.clone(),
+                        var_dec.declare_type,
+                    );
                 }
             }
             // function vm declaration
-            buf.push(format!("function {}.{} {}",node.name,sub.name,self.subroutine_table.var_counter));
-
+            buf.push(format!(
+                "function {}.{} {}",
+                node.name, sub.name, self.subroutine_table.var_counter
+            ));
 
-            match sub.subroutine_type{
+            match sub.subroutine_type {
                 SubroutineType::Constructor => {
-                    buf.push(format!("push constant {}",self.class_table.field_counter)); // size of the class
+                    buf.push(format!("push constant {}", self.class_table.field_counter)); // size of the class
                     buf.push("call Memory.alloc 1".to_string()); // alloc object
                     buf.push("pop pointer 0".to_string());
-                },
+                }
                 SubroutineType::Method => {
                     buf.push("push argument 0".to_string()); // need to set this with implicit argument 0 on methods
                     buf.push("pop pointer 0".to_string());
-                },
+                }
                 SubroutineType::Function => {
                     // nothing needed here i think
-                },
+                }
             }
             // handle body:
-            for statement in sub.body.body{
+            for statement in sub.body.body {
                 buf.push(self.statement_to_vm_code(statement));
             }
 
             // reset subroutine table
             self.subroutine_table = Scope {
                 table: HashMap::new(),
+                constants: HashMap::new(),
                 static_counter: 0,
                 field_counter: 0,
                 var_counter: 0,
@@ -843,12 +1360,116 @@ impl CodeGenerator {
             };
         }
 
+        // accessors for the fields other classes may use as obj.field:
+        // function Class.get$field 0   function Class.set$field 0
+        // push argument 0              push argument 0
+        // pop pointer 0                pop pointer 0
+        // push this n                  push argument 1
+        // return                       pop this n
+        //                              push constant 0
+        //                              return
+        let fields = self
+            .index
+            .classes
+            .get(&node.name)
+            .map_or(&[][..], |info| info.fields.as_slice());
+        for (field, _) in fields {
+            let number = self.class_table.table[field].number;
+            buf.push(format!("function {}.get${} 0", node.name, field));
+            buf.push("push argument 0".to_string());
+            buf.push("pop pointer 0".to_string());
+            buf.push(format!("push this {}", number));
+            buf.push("return".to_string());
+            buf.push(format!("function {}.set${} 0", node.name, field));
+            buf.push("push argument 0".to_string());
+            buf.push("pop pointer 0".to_string());
+            buf.push("push argument 1".to_string());
+            buf.push(format!("pop this {}", number));
+            buf.push("push constant 0".to_string());
+            buf.push("return".to_string());
+        }
+
         buf.join("\n")
     }
-    fn statement_to_vm_code(&mut self,node: Statement) -> String{
+    /// A variable of the subroutine or the class, the semantic pass made sure it exists
+    fn variable(&self, name: &str) -> Variable {
+        match self.subroutine_table.table.get(name) {
+            Some(var) => var.clone(),
+            None => match self.class_table.table.get(name) {
+                Some(var) => var.clone(),
+                None => panic!("var {} not found in class or subroutine", name),
+            },
+        }
+    }
+    /// Pushes the address of `array_name[i][j]...`. Every element but the last is read
+    /// through pointer 1 before the next index runs, so indices may hold array accesses and
+    /// calls themselves
+    /// push arr
+    /// VM code for computing and pushing the value of index 1
+    /// add
+    /// pop pointer 1 // only for further indices
+    /// push that 0
+    /// VM code for computing and pushing the value of index 2
+    /// add
+    fn element_address(&mut self, array_name: &str, indices: Vec<Expression>) -> String {
+        let mut buf: Vec<String> = vec![];
+        buf.push(self.name_to_vm_code(array_name));
+        for (idx, index) in indices.into_iter().enumerate() {
+            if idx > 0 {
+                buf.push("pop pointer 1".to_string());
+                buf.push("push that 0".to_string());
+            }
+            buf.push(self.expression_to_vm_code(index));
+            buf.push("add".to_string());
+        }
+        buf.join("\n")
+    }
+    /// Value of a constant or enum value, unless a variable hides it
+    fn constant(&self, name: &str) -> Option<i16> {
+        let variable = variable_part(name);
+        if self.subroutine_table.table.contains_key(variable)
+            || self.class_table.table.contains_key(variable)
+        {
+            return None;
+        }
+        self.class_table.constants.get(name).copied()
+    }
+    /// `obj.field` as the object and the class whose accessors reach the field
+    fn field_access<'n>(&self, name: &'n str) -> Option<(Variable, String, &'n str)> {
+        let (object, field) = name.split_once('.')?;
+        if self.constant(name).is_some() {
+            return None;
+        }
+        let object = self.variable(object);
+        let Type::ClassName(class_name) = &object.v_type else {
+            unreachable!("the semantic pass only allows fields of objects")
+        };
+        let class_name = class_name.clone();
+        Some((object, class_name, field))
+    }
+    /// Pushes the value of a variable, a constant or `obj.field`:
+    /// push obj
+    /// call Class.get$field 1
+    fn name_to_vm_code(&mut self, name: &str) -> String {
+        if let Some(value) = self.constant(name) {
+            return self.term_to_vm_code(constant_term(value));
+        }
+        if let Some((object, class_name, field)) = self.field_access(name) {
+            return format!(
+                "push {} {}\ncall {}.get${} 1",
+                object.kind.segment(),
+                object.number,
+                class_name,
+                field
+            );
+        }
+        let var = self.variable(name);
+        format!("push {} {}", var.kind.segment(), var.number)
+    }
+    fn statement_to_vm_code(&mut self, node: Statement) -> String {
         let mut buf: Vec<String> = vec![];
 
-        match node{
+        match node {
             Statement::Let(let_statement) => {
                 // if array access then deal with it this way!:
                 // let arr[expression 1] = expression 2
@@ -860,69 +1481,99 @@ impl CodeGenerator {
                 // pop pointer 1
                 // push temp 0
                 // pop that 0
-                let var_to_assign_to;
-                let num_temp = self.subroutine_table.table.get(&let_statement.var_name);
-                if let Some(var) = num_temp{
-                    var_to_assign_to = var.clone();
-                }else if let Some(var) = self.class_table.table.get(&let_statement.var_name){
-                    var_to_assign_to = var.clone();
-                } else {
-                    panic!("var not found in class or subroutine to assign to with let statement");
-                }
+                let compound = let_statement.compound.map(|op| match op.as_str() {
+                    "+=" | "++" => "add",
+                    _ => "sub",
+                });
 
-                if let_statement.array_acces.is_none(){
-                    buf.push(self.expression_to_vm_code(let_statement.bind_to));
-                    buf.push(format!("pop {} {}",var_to_assign_to.kind.to_string(),var_to_assign_to.number));
-                }else{
-                    // Array access TODO
-                    buf.push(self.expression_to_vm_code(let_statement.bind_to));
-                    buf.push("pop temp 0".to_string());
-                    {
-                        let index = let_statement.array_acces.unwrap();
-                        let arr_var_to_use;
-                        if let Some(var) = self.subroutine_table.table.get(&let_statement.var_name){
-                            arr_var_to_use = var.clone();
-                        }else if let Some(var) = self.class_table.table.get(&let_statement.var_name){
-                            arr_var_to_use = var.clone();
-                        } else {
-                            panic!("var not found in class or subroutine to assign to with Let Statement with array");
+                if !let_statement.indices.is_empty() {
+                    let address =
+                        self.element_address(&let_statement.var_name, let_statement.indices);
+                    if let Some(compound) = compound {
+                        // let arr[expression 1] += expression 2, the value goes first
+                        // VM code for computing and pushing the value of expression 2
+                        // address of arr[expression 1] like above
+                        // pop pointer 1
+                        // push that 0
+                        // add // sub then neg for -=, the old value is on top
+                        // pop that 0
+                        buf.push(self.expression_to_vm_code(let_statement.bind_to));
+                        buf.push(address);
+                        buf.push("pop pointer 1".to_string());
+                        buf.push("push that 0".to_string());
+                        buf.push(compound.to_string());
+                        if compound == "sub" {
+                            buf.push("neg".to_string());
                         }
-        
-                        buf.push(format!("push {} {}",arr_var_to_use.kind.to_string(),arr_var_to_use.number));
-                        buf.push(self.expression_to_vm_code(index));
-                        buf.push("add".to_string());
+                    } else {
+                        // the address stays on the stack while expression 2 runs, temp 0 and
+                        // pointer 1 are only used after it, calls in it cannot change them
+                        buf.push(address);
+                        buf.push(self.expression_to_vm_code(let_statement.bind_to));
+                        buf.push("pop temp 0".to_string());
                         buf.push("pop pointer 1".to_string());
                         buf.push("push temp 0".to_string());
-                        buf.push("pop that 0".to_string());
                     }
+                    buf.push("pop that 0".to_string());
+                } else if let Some((object, class_name, field)) =
+                    self.field_access(&let_statement.var_name)
+                {
+                    // let obj.field = expression
+                    // push obj
+                    // VM code for computing and pushing the value of expression
+                    // call Class.set$field 2
+                    // pop temp 0 // the setter returns 0
+                    let push_object = format!("push {} {}", object.kind.segment(), object.number);
+                    buf.push(push_object.clone());
+                    if let Some(compound) = compound {
+                        buf.push(push_object);
+                        buf.push(format!("call {}.get${} 1", class_name, field));
+                        buf.push(self.expression_to_vm_code(let_statement.bind_to));
+                        buf.push(compound.to_string());
+                    } else {
+                        buf.push(self.expression_to_vm_code(let_statement.bind_to));
+                    }
+                    buf.push(format!("call {}.set${} 2", class_name, field));
+                    buf.push("pop temp 0".to_string());
+                } else {
+                    let var_to_assign_to = self.variable(&let_statement.var_name);
+                    let segment = var_to_assign_to.kind.segment();
+                    if let Some(compound) = compound {
+                        buf.push(format!("push {} {}", segment, var_to_assign_to.number));
+                        buf.push(self.expression_to_vm_code(let_statement.bind_to));
+                        buf.push(compound.to_string());
+                    } else {
+                        buf.push(self.expression_to_vm_code(let_statement.bind_to));
+                    }
+                    buf.push(format!("pop {} {}", segment, var_to_assign_to.number));
                 }
-            },
+            }
             Statement::If(if_statement) => {
-                let label1 = format!("Label_{}",&self.label_counter);
+                let label1 = format!("Label_{}", &self.label_counter);
                 self.label_counter += 1;
-                let label2 = format!("Label_{}",&self.label_counter);
+                let label2 = format!("Label_{}", &self.label_counter);
                 self.label_counter += 1;
 
                 buf.push(self.expression_to_vm_code(if_statement.condition));
 
                 buf.push("not".to_string());
 
-                buf.push(format!("if-goto {}",label1));
+                buf.push(format!("if-goto {}", label1));
 
-                for statement in if_statement.if_true{
+                for statement in if_statement.if_true {
                     buf.push(self.statement_to_vm_code(statement));
                 }
-                buf.push(format!("goto {}",label2));
-                buf.push(format!("label {}",label1));
+                buf.push(format!("goto {}", label2));
+                buf.push(format!("label {}", label1));
 
-                if let Some(else_statements) = if_statement.else_part{
-                    for statement in else_statements{
+                if let Some(else_statements) = if_statement.else_part {
+                    for statement in else_statements {
                         buf.push(self.statement_to_vm_code(statement));
                     }
                 }
 
-                buf.push(format!("label {}",label2));
-            },
+                buf.push(format!("label {}", label2));
+            }
             Statement::While(while_statement) => {
                 // label L1
                 // compiled (expression)
@@ -932,26 +1583,143 @@ impl CodeGenerator {
                 // goto L1
                 // label L2
 
-                let label1 = format!("Label_{}",&self.label_counter);
+                let label1 = format!("Label_{}", &self.label_counter);
                 self.label_counter += 1;
-                let label2 = format!("Label_{}",&self.label_counter);
+                let label2 = format!("Label_{}", &self.label_counter);
                 self.label_counter += 1;
 
-                buf.push(format!("label {}",label1));
+                buf.push(format!("label {}", label1));
 
                 buf.push(self.expression_to_vm_code(while_statement.condition));
 
                 buf.push("not".to_string());
 
-                buf.push(format!("if-goto {}",label2));
+                buf.push(format!("if-goto {}", label2));
 
-                for statement in while_statement.if_true{
+                self.loop_labels
+                    .push((Some(label1.clone()), label2.clone()));
+                for statement in while_statement.if_true {
                     buf.push(self.statement_to_vm_code(statement));
                 }
-                buf.push(format!("goto {}",label1));
+                self.loop_labels.pop();
+                buf.push(format!("goto {}", label1));
 
-                buf.push(format!("label {}",label2));
-            },
+                buf.push(format!("label {}", label2));
+            }
+            Statement::For(for_statement) => {
+                // compiled (init)
+                // label L1
+                // compiled (expression)
+                // not
+                // if-goto L3
+                // compiled (statements)
+                // label L2 // continue jumps here
+                // compiled (step)
+                // goto L1
+                // label L3
+
+                let label1 = format!("Label_{}", &self.label_counter);
+                self.label_counter += 1;
+                let label2 = format!("Label_{}", &self.label_counter);
+                self.label_counter += 1;
+                let label3 = format!("Label_{}", &self.label_counter);
+                self.label_counter += 1;
+
+                if let Some(init) = for_statement.init {
+                    buf.push(self.statement_to_vm_code(Statement::Let(init)));
+                }
+                buf.push(format!("label {}", label1));
+
+                // no condition loops until a break
+                if let Some(condition) = for_statement.condition {
+                    buf.push(self.expression_to_vm_code(condition));
+                    buf.push("not".to_string());
+                    buf.push(format!("if-goto {}", label3));
+                }
+
+                self.loop_labels
+                    .push((Some(label2.clone()), label3.clone()));
+                for statement in for_statement.body {
+                    buf.push(self.statement_to_vm_code(statement));
+                }
+                self.loop_labels.pop();
+
+                buf.push(format!("label {}", label2));
+                if let Some(step) = for_statement.step {
+                    buf.push(self.statement_to_vm_code(Statement::Let(step)));
+                }
+                buf.push(format!("goto {}", label1));
+
+                buf.push(format!("label {}", label3));
+            }
+            Statement::Break => {
+                let (_, break_label) = self
+                    .loop_labels
+                    .last()
+                    .expect("the parser only allows break in loops");
+                buf.push(format!("goto {}", break_label));
+            }
+            Statement::Continue => {
+                let continue_label = self
+                    .loop_labels
+                    .iter()
+                    .rev()
+                    .find_map(|(continue_label, _)| continue_label.as_ref())
+                    .expect("the parser only allows continue in loops");
+                buf.push(format!("goto {}", continue_label));
+            }
+            Statement::Switch(switch_statement) => {
+                // compiled (expression)
+                // pop temp 0
+                // push temp 0 // for every case
+                // push constant N
+                // eq
+                // if-goto L_case
+                // goto L_default // or L_end without a default
+                // label L_case
+                // compiled (statements) // runs on into the next arm
+                // label L_end // break jumps here
+                // the case values are constants, so nothing can touch temp 0 in between
+
+                buf.push(self.expression_to_vm_code(switch_statement.value));
+                buf.push("pop temp 0".to_string());
+
+                let end_label = format!("Label_{}", &self.label_counter);
+                self.label_counter += 1;
+                let mut arm_labels = vec![];
+                let mut default_label = None;
+                for arm in &switch_statement.arms {
+                    let arm_label = format!("Label_{}", &self.label_counter);
+                    self.label_counter += 1;
+                    match &arm.label {
+                        Some(label) => {
+                            let value = resolved_value(label, &|name| self.constant(name))
+                                .expect("the semantic pass only allows constant case values");
+                            buf.push("push temp 0".to_string());
+                            buf.push(self.term_to_vm_code(constant_term(value)));
+                            buf.push("eq".to_string());
+                            buf.push(format!("if-goto {}", arm_label));
+                        }
+                        None => default_label = Some(arm_label.clone()),
+                    }
+                    arm_labels.push(arm_label);
+                }
+                buf.push(format!(
+                    "goto {}",
+                    default_label.as_ref().unwrap_or(&end_label)
+                ));
+
+                self.loop_labels.push((None, end_label.clone()));
+                for (arm, arm_label) in switch_statement.arms.into_iter().zip(arm_labels) {
+                    buf.push(format!("label {}", arm_label));
+                    for statement in arm.body {
+                        buf.push(self.statement_to_vm_code(statement));
+                    }
+                }
+                self.loop_labels.pop();
+
+                buf.push(format!("label {}", end_label));
+            }
             Statement::Do(do_statement) => {
                 let subroutine_call = do_statement.call;
 
@@ -959,190 +1727,208 @@ impl CodeGenerator {
 
                 // then pop the return value away!
                 buf.push("pop temp 0".to_owned());
-            },
+            }
             Statement::Return(return_statement) => {
-                if let Some(return_expr) = return_statement.value{
+                if let Some(return_expr) = return_statement.value {
                     buf.push(self.expression_to_vm_code(return_expr));
                     buf.push("return".to_owned());
-                }else{
+                } else {
                     buf.push("push constant 0".to_owned());
                     buf.push("return".to_owned());
                 }
-            },
+            }
         }
 
-
         buf.join("\n")
     }
-    fn expression_to_vm_code(&mut self,node: Expression) -> String{
+    fn expression_to_vm_code(&mut self, node: Expression) -> String {
         let mut buf: Vec<String> = vec![self.term_to_vm_code(node.intial_term)];
 
-        for afterwards in node.afterwards{
+        for afterwards in node.afterwards {
             buf.push(self.term_to_vm_code(afterwards.1));
             // emit op:
             match afterwards.0.as_str() {
                 "+" => {
                     buf.push("add".to_string());
-                },
+                }
                 "-" => {
                     buf.push("sub".to_string());
-                },
+                }
                 "*" => {
                     buf.push("call Math.multiply 2".to_string());
-                },
+                }
                 "/" => {
                     buf.push("call Math.divide 2".to_string());
-                },
+                }
                 "&" => {
                     buf.push("and".to_string());
-                },
+                }
                 "|" => {
                     buf.push("or".to_string());
-                },
+                }
                 ">" => {
                     buf.push("gt".to_string());
-                },
+                }
                 "<" => {
                     buf.push("lt".to_string());
-                },
+                }
                 "=" => {
                     buf.push("eq".to_string());
-                },
-                _ => unreachable!()
+                }
+                _ => unreachable!(),
             }
         }
 
         buf.join("\n")
     }
-    fn term_to_vm_code(&mut self,node: Term) -> String{
+    fn term_to_vm_code(&mut self, node: Term) -> String {
         let mut buf: Vec<String> = vec![];
 
         match node {
-            Term::IntegerConstant(int) => buf.push(format!("push constant {}",int)),
+            Term::IntegerConstant(int) => buf.push(format!("push constant {}", int)),
+            Term::CharConstant(character) => {
+                buf.push(format!("push constant {}", char_code(character)))
+            }
             Term::StringConstant(string_const) => {
-                let len = string_const.len();
+                // the tokenizer only lets printable ASCII and escaped newlines through
+                let len = string_const.chars().count();
 
-                buf.push(format!("push constant {}",len));
+                buf.push(format!("push constant {}", len));
 
                 // call string.new
                 buf.push("call String.new 1".to_string());
                 // append all chars
-                for character in string_const.chars(){
-                    buf.push(format!("push constant {}",character as u8));
+                for character in string_const.chars() {
+                    buf.push(format!("push constant {}", char_code(character)));
                     buf.push("call String.appendChar 2".to_string());
                 }
-
-            },
-            Term::KeywordConstant(key) => {
-                match key.as_str(){
-                    "false" => {
-                        buf.push("push constant 0".to_string());
-                    },
-                    "true" => {
-                        buf.push("push constant 1".to_string());
-                        buf.push("neg".to_string());
-                    },
-                    "null" => {
-                        buf.push("push constant 0".to_string());
-                    },
-                    "this" => {
-                        buf.push("push pointer 0".to_string());
-                    },
-                    _ => unreachable!()
+            }
+            Term::KeywordConstant(key) => match key.as_str() {
+                "false" => {
+                    buf.push("push constant 0".to_string());
                 }
-            },
-            Term::VarName(var_name) => {
-                let var_to_use;
-                if let Some(var) = self.subroutine_table.table.get(&var_name){
-                    var_to_use = var.clone();
-                }else if let Some(var) = self.class_table.table.get(&var_name){
-                    var_to_use = var.clone();
-                } else {
-                    panic!("var not found in class or subroutine to assign to with Term::VarName");
+                "true" => {
+                    // true is -1, all bits set
+                    buf.push("push constant 0".to_string());
+                    buf.push("not".to_string());
                 }
-
-                buf.push(format!("push {} {}",var_to_use.kind.to_string(),var_to_use.number))
+                "null" => {
+                    buf.push("push constant 0".to_string());
+                }
+                "this" => {
+                    buf.push("push pointer 0".to_string());
+                }
+                _ => unreachable!(),
             },
-            Term::ArrayAccess { array_name, index } => {
-                // let arr[expression 1] = expression 2
-                // push arr
-                // VM code for computing and pushing the value of expression 1
-                // add // top stack value = RAM address of arr[expression1]
-                // VM code for computing and pushing the value of expression 2
-                // pop temp 0 // temp 0 = the value of expression2
+            Term::VarName(var_name, _) => {
+                buf.push(self.name_to_vm_code(&var_name));
+            }
+            Term::ArrayAccess {
+                array_name,
+                indices,
+                ..
+            } => {
+                buf.push(self.element_address(&array_name, indices));
+                buf.push("pop pointer 1".to_string());
+                buf.push("push that 0".to_string());
+            }
+            Term::ArrayLiteral { elements, .. } => {
+                // all elements are on the stack before the array exists, so calls in them
+                // cannot disturb pointer 1
+                // VM code for every element
+                // push constant n
+                // call Array.new 1
                 // pop pointer 1
-                // push temp 0
-                // pop that 0
-                let arr_var_to_use;
-                if let Some(var) = self.subroutine_table.table.get(&array_name){
-                    arr_var_to_use = var.clone();
-                }else if let Some(var) = self.class_table.table.get(&array_name){
-                    arr_var_to_use = var.clone();
-                } else {
-                    panic!("var not found in class or subroutine to assign to with Term::ArrayAccess");
+                // pop that n-1 ... pop that 0
+                // push pointer 1
+                let len = elements.len();
+                for element in elements {
+                    buf.push(self.expression_to_vm_code(element));
                 }
-
-                buf.push(format!("push {} {}",arr_var_to_use.kind.to_string(),arr_var_to_use.number));
-
-                buf.push(self.expression_to_vm_code(*index));
-                buf.push("add".to_string());
+                buf.push(format!("push constant {}", len));
+                buf.push("call Array.new 1".to_string());
                 buf.push("pop pointer 1".to_string());
-                buf.push("push that 0".to_string());
-            },
+                for idx in (0..len).rev() {
+                    buf.push(format!("pop that {}", idx));
+                }
+                buf.push("push pointer 1".to_string());
+            }
             Term::SubroutineCall(call) => {
                 buf.push(self.sub_call_to_vm_code(call));
-            },
+            }
             Term::Expression(expr) => {
                 buf.push(self.expression_to_vm_code(*expr));
-            },
+            }
+            // 32768 is not a valid constant, but its complement is
+            Term::UnaryOp { op, term } if matches!(*term, Term::IntegerConstant(32768)) => {
+                debug_assert_eq!(op, "-");
+                buf.push("push constant 32767".to_string());
+                buf.push("not".to_string());
+            }
             Term::UnaryOp { op, term } => {
                 buf.push(self.term_to_vm_code(*term));
-                buf.push(match op.as_str(){
+                buf.push(match op.as_str() {
                     "-" => "neg".to_string(),
                     "~" => "not".to_string(),
-                    _ => unreachable!()
+                    _ => unreachable!(),
                 });
-            },
+            }
+            Term::Store { slot, term } => {
+                buf.push(self.term_to_vm_code(*term));
+                buf.push(format!("pop temp {}", slot));
+                buf.push(format!("push temp {}", slot));
+            }
+            Term::Load(slot) => buf.push(format!("push temp {}", slot)),
         }
 
-
         buf.join("\n")
     }
-    fn sub_call_to_vm_code(&mut self,call: SubroutineCall) -> String{
+    fn sub_call_to_vm_code(&mut self, call: SubroutineCall) -> String {
         let mut buf: Vec<String> = vec![];
         // Inject Argument for this if it is a method call :)
-        let mut class_of_callee = "IMPOSSIBLE".to_string();
+        let class_of_callee;
         let mut know_method_call_inc = 0;
-        if call.obj_name.is_some(){
-            if let Some(var) = self.subroutine_table.table.get(&call.obj_name.as_ref().unwrap().clone()){
-                buf.push(format!("push {} {}",var.kind.to_string(),var.number));
-                know_method_call_inc = 1;
-                if let Type::ClassName(name) = var.clone().v_type{
-                    class_of_callee = name;
-                }
-            }else if let Some(var) = self.class_table.table.get(&call.obj_name.as_ref().unwrap().clone()){
-                buf.push(format!("push {} {}",var.kind.to_string(),var.number));
+        if let Some(obj_name) = call.obj_name {
+            let var = self
+                .subroutine_table
+                .table
+                .get(&obj_name)
+                .or_else(|| self.class_table.table.get(&obj_name));
+            if let Some(var) = var {
+                buf.push(format!("push {} {}", var.kind.segment(), var.number));
                 know_method_call_inc = 1;
-                if let Type::ClassName(name) = var.clone().v_type{
-                    class_of_callee = name;
-                }
+                class_of_callee = match &var.v_type {
+                    Type::ClassName(name) => name.clone(),
+                    _ => unreachable!("the semantic pass only allows calls on objects"),
+                };
             } else {
-                class_of_callee = call.obj_name.unwrap();
-                //eprintln!("var not found in class or subroutine to assign to with Term::VarName");
+                class_of_callee = obj_name;
             }
-        }else{
-            // if it is a local do call we just push 0 as first argument
+        } else {
+            // a local call only gets this as first argument if the callee is a method
             class_of_callee = self.main_class_name.clone();
-            know_method_call_inc = 1;
-            buf.push("push pointer 0".to_string());
+            let is_method = self
+                .index
+                .classes
+                .get(&class_of_callee)
+                .and_then(|class| class.subroutines.get(&call.subroutine_name))
+                .is_some_and(|sub| matches!(sub.kind, SubroutineType::Method));
+            if is_method {
+                know_method_call_inc = 1;
+                buf.push("push pointer 0".to_string());
+            }
         }
 
-        for argument in &call.arguments.list{
+        for argument in &call.arguments.list {
             buf.push(self.expression_to_vm_code(argument.clone()));
         }
 
-        buf.push(format!("call {}.{} {}",class_of_callee,call.subroutine_name,call.arguments.list.len() + know_method_call_inc));
-
+        buf.push(format!(
+            "call {}.{} {}",
+            class_of_callee,
+            call.subroutine_name,
+            call.arguments.list.len() + know_method_call_inc
+        ));
 
         buf.join("\n")
     }
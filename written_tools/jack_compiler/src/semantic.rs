@@ -0,0 +1,1163 @@
+//! Semantic checks between parsing and code generation.
+//!
+//! [`ProgramIndex`] knows the subroutine signatures of every class in the program plus the
+//! Jack OS, [`ProgramIndex::check_class`] then resolves every name in one class against it
+//! and type checks its statements. Jack is loosely typed, so the rules only reject what can
+//! never work: `int`, `char` and `boolean` mix freely, an `int` doubles as an object address
+//! and `Array` stands in for any object.
+//!
+//! With [`Typing::Strict`] values have to match their declared types: a `char` is an `int`
+//! but not the other way round unless it is a constant, conditions are `boolean`, arithmetic
+//! takes numbers and an object is only its own class, `null`, or passed where the OS takes
+//! any object as an `Array`, like `Memory.deAlloc(this)`. Strict classes also get
+//! accessors for their fields, which lets other classes write `obj.field`.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    constants::{class_constants, resolved_value, variable_part},
+    error::CompileError,
+    parser_non_xml::{
+        Class, ConstDec, DeclareType, Expression, Let, Statement, SubroutineCall, SubroutineDec,
+        SubroutineType, Term, Type,
+    },
+    tokenizer::Span,
+};
+
+/// Signatures of the Jack OS: class, kind, return type, name and parameter types
+const OS_API: &[(&str, &str, &str, &str, &[&str])] = &[
+    ("Math", "function", "void", "init", &[]),
+    ("Math", "function", "int", "abs", &["int"]),
+    ("Math", "function", "int", "multiply", &["int", "int"]),
+    ("Math", "function", "int", "divide", &["int", "int"]),
+    ("Math", "function", "int", "min", &["int", "int"]),
+    ("Math", "function", "int", "max", &["int", "int"]),
+    ("Math", "function", "int", "sqrt", &["int"]),
+    ("String", "constructor", "String", "new", &["int"]),
+    ("String", "method", "void", "dispose", &[]),
+    ("String", "method", "int", "length", &[]),
+    ("String", "method", "char", "charAt", &["int"]),
+    ("String", "method", "void", "setCharAt", &["int", "char"]),
+    ("String", "method", "String", "appendChar", &["char"]),
+    ("String", "method", "void", "eraseLastChar", &[]),
+    ("String", "method", "int", "intValue", &[]),
+    ("String", "method", "void", "setInt", &["int"]),
+    ("String", "function", "char", "backSpace", &[]),
+    ("String", "function", "char", "doubleQuote", &[]),
+    ("String", "function", "char", "newLine", &[]),
+    ("Array", "function", "Array", "new", &["int"]),
+    ("Array", "method", "void", "dispose", &[]),
+    ("Output", "function", "void", "init", &[]),
+    ("Output", "function", "void", "moveCursor", &["int", "int"]),
+    ("Output", "function", "void", "printChar", &["char"]),
+    ("Output", "function", "void", "printString", &["String"]),
+    ("Output", "function", "void", "printInt", &["int"]),
+    ("Output", "function", "void", "println", &[]),
+    ("Output", "function", "void", "backSpace", &[]),
+    ("Screen", "function", "void", "init", &[]),
+    ("Screen", "function", "void", "clearScreen", &[]),
+    ("Screen", "function", "void", "setColor", &["boolean"]),
+    ("Screen", "function", "void", "drawPixel", &["int", "int"]),
+    (
+        "Screen",
+        "function",
+        "void",
+        "drawLine",
+        &["int", "int", "int", "int"],
+    ),
+    (
+        "Screen",
+        "function",
+        "void",
+        "drawRectangle",
+        &["int", "int", "int", "int"],
+    ),
+    (
+        "Screen",
+        "function",
+        "void",
+        "drawCircle",
+        &["int", "int", "int"],
+    ),
+    ("Keyboard", "function", "void", "init", &[]),
+    ("Keyboard", "function", "char", "keyPressed", &[]),
+    ("Keyboard", "function", "char", "readChar", &[]),
+    ("Keyboard", "function", "String", "readLine", &["String"]),
+    ("Keyboard", "function", "int", "readInt", &["String"]),
+    ("Memory", "function", "void", "init", &[]),
+    ("Memory", "function", "int", "peek", &["int"]),
+    ("Memory", "function", "void", "poke", &["int", "int"]),
+    ("Memory", "function", "Array", "alloc", &["int"]),
+    ("Memory", "function", "void", "deAlloc", &["Array"]),
+    ("Sys", "function", "void", "init", &[]),
+    ("Sys", "function", "void", "halt", &[]),
+    ("Sys", "function", "void", "error", &["int"]),
+    ("Sys", "function", "void", "wait", &["int"]),
+];
+
+pub fn type_from_name(name: &str) -> Type {
+    match name {
+        "int" => Type::Int,
+        "char" => Type::Char,
+        "boolean" => Type::Boolean,
+        "void" => Type::Void,
+        class_name => Type::ClassName(class_name.to_string()),
+    }
+}
+
+pub fn type_name(type_type: &Type) -> &str {
+    match type_type {
+        Type::Int => "int",
+        Type::Char => "char",
+        Type::Boolean => "boolean",
+        Type::Void => "void",
+        Type::ClassName(name) => name,
+    }
+}
+
+pub fn kind_name(kind: SubroutineType) -> &'static str {
+    match kind {
+        SubroutineType::Constructor => "constructor",
+        SubroutineType::Function => "function",
+        SubroutineType::Method => "method",
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubroutineInfo {
+    pub kind: SubroutineType,
+    pub return_type: Type,
+    pub params: Vec<Type>,
+}
+
+/// How closely values have to match their declared types
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Typing {
+    /// Jack as specified, see the module documentation
+    #[default]
+    Loose,
+    Strict,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ClassInfo {
+    pub subroutines: HashMap<String, SubroutineInfo>,
+    /// Fields other classes reach through generated accessors as `obj.field`, only classes
+    /// compiled with strict typing have them
+    pub fields: Vec<(String, Type)>,
+    /// `false` for the built in OS classes, a program may replace those with its own
+    pub user_defined: bool,
+}
+
+/// Every class of the program by name
+#[derive(Debug, Clone)]
+pub struct ProgramIndex {
+    pub classes: HashMap<String, ClassInfo>,
+}
+
+impl ProgramIndex {
+    /// An index that only knows the Jack OS
+    pub fn new() -> ProgramIndex {
+        let mut classes: HashMap<String, ClassInfo> = HashMap::new();
+        for (class_name, kind, return_type, name, params) in OS_API {
+            classes
+                .entry(class_name.to_string())
+                .or_default()
+                .subroutines
+                .insert(
+                    name.to_string(),
+                    SubroutineInfo {
+                        kind: match *kind {
+                            "constructor" => SubroutineType::Constructor,
+                            "method" => SubroutineType::Method,
+                            _ => SubroutineType::Function,
+                        },
+                        return_type: type_from_name(return_type),
+                        params: params.iter().map(|param| type_from_name(param)).collect(),
+                    },
+                );
+        }
+        ProgramIndex { classes }
+    }
+
+    /// Adds the signatures of `class`, replacing an OS class of the same name
+    pub fn add_class(&mut self, class: &Class, typing: Typing) -> Vec<CompileError> {
+        let mut errors = vec![];
+        if self
+            .classes
+            .get(&class.name)
+            .is_some_and(|info| info.user_defined)
+        {
+            errors.push(CompileError::new(
+                format!("class {} is defined more than once", class.name),
+                class.span,
+            ));
+            return errors;
+        }
+
+        let mut subroutines = HashMap::new();
+        for sub in &class.sub_dec {
+            if subroutines.contains_key(&sub.name) {
+                errors.push(CompileError::new(
+                    format!(
+                        "subroutine {}.{} is defined more than once",
+                        class.name, sub.name
+                    ),
+                    sub.span,
+                ));
+                continue;
+            }
+            subroutines.insert(
+                sub.name.clone(),
+                SubroutineInfo {
+                    kind: sub.subroutine_type,
+                    return_type: sub.return_type.clone(),
+                    params: sub
+                        .parameter_list
+                        .list
+                        .iter()
+                        .map(|(type_type, _)| type_type.clone())
+                        .collect(),
+                },
+            );
+        }
+        let mut fields = vec![];
+        if typing == Typing::Strict {
+            for var_dec in &class.var_dec {
+                if matches!(var_dec.declare_type, DeclareType::Field) {
+                    fields.extend(
+                        var_dec
+                            .variable_names
+                            .iter()
+                            .map(|name| (name.clone(), var_dec.type_type.clone())),
+                    );
+                }
+            }
+        }
+        self.add_interface(&class.name, subroutines, fields);
+
+        errors
+    }
+
+    /// Adds a class known only by its signatures and fields, like one loaded from the cache
+    pub fn add_interface(
+        &mut self,
+        class_name: &str,
+        subroutines: HashMap<String, SubroutineInfo>,
+        fields: Vec<(String, Type)>,
+    ) {
+        self.classes.insert(
+            class_name.to_string(),
+            ClassInfo {
+                subroutines,
+                fields,
+                user_defined: true,
+            },
+        );
+    }
+
+    /// Resolves every name in `class` and type checks it
+    pub fn check_class(&self, class: &Class, typing: Typing) -> ClassCheck {
+        let mut checker = Checker {
+            index: self,
+            class,
+            typing,
+            class_vars: HashMap::new(),
+            constants: HashMap::new(),
+            constant_values: class_constants(class),
+            locals: HashMap::new(),
+            subroutine: None,
+            errors: vec![],
+            dependencies: BTreeSet::new(),
+        };
+        checker.class();
+        ClassCheck {
+            errors: checker.errors,
+            dependencies: checker.dependencies,
+        }
+    }
+}
+
+pub struct ClassCheck {
+    pub errors: Vec<CompileError>,
+    /// Other classes whose signatures the result depends on
+    pub dependencies: BTreeSet<String>,
+}
+
+/// What an expression evaluates to, as far as it can be known
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value {
+    Known(Type),
+    Null,
+    Unknown,
+}
+
+/// Whether a value of type `actual` may be stored where `expected` is declared
+fn compatible(expected: &Type, actual: &Value) -> bool {
+    let actual = match actual {
+        Value::Unknown => return true,
+        Value::Null => return !matches!(expected, Type::Void),
+        Value::Known(actual) => actual,
+    };
+    let primitive = |type_type: &Type| matches!(type_type, Type::Int | Type::Char | Type::Boolean);
+    match (expected, actual) {
+        (Type::Void, _) | (_, Type::Void) => false,
+        _ if primitive(expected) && primitive(actual) => true,
+        (Type::ClassName(expected), Type::ClassName(actual)) => {
+            expected == actual || expected == "Array" || actual == "Array"
+        }
+        (Type::Int, Type::ClassName(_)) | (Type::ClassName(_), Type::Int) => true,
+        _ => false,
+    }
+}
+
+/// [`compatible`] for strict typing: only a `char` may be used as an `int`, `null` and any
+/// object as an `Array`. A value of unknown type like an array element goes anywhere
+fn strictly_compatible(expected: &Type, actual: &Value) -> bool {
+    let actual = match actual {
+        Value::Unknown => return true,
+        Value::Null => return matches!(expected, Type::ClassName(_)),
+        Value::Known(actual) => actual,
+    };
+    match (expected, actual) {
+        (Type::Int, Type::Char) => true,
+        (Type::ClassName(expected), Type::ClassName(_)) if expected == "Array" => true,
+        _ => actual == expected,
+    }
+}
+
+fn numeric(value: &Value) -> bool {
+    matches!(value, Value::Known(Type::Int | Type::Char) | Value::Unknown)
+}
+
+fn boolean(value: &Value) -> bool {
+    matches!(value, Value::Known(Type::Boolean) | Value::Unknown)
+}
+
+/// Whether strict typing allows `left op right`
+fn strict_operands(op: &str, left: &Value, right: &Value) -> bool {
+    match op {
+        "&" | "|" => (boolean(left) && boolean(right)) || (numeric(left) && numeric(right)),
+        "=" => {
+            (numeric(left) && numeric(right))
+                || (boolean(left) && boolean(right))
+                || match (left, right) {
+                    (Value::Null, _) | (_, Value::Null) => true,
+                    (Value::Known(Type::ClassName(left)), Value::Known(Type::ClassName(right))) => {
+                        left == right
+                    }
+                    _ => false,
+                }
+        }
+        _ => numeric(left) && numeric(right),
+    }
+}
+
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Known(type_type) => type_name(type_type).to_string(),
+        Value::Null => "null".to_string(),
+        Value::Unknown => "an unknown type".to_string(),
+    }
+}
+
+/// Whether every path through `statements` ends in a return
+fn always_returns(statements: &[Statement]) -> bool {
+    match statements.last() {
+        Some(Statement::Return(_)) => true,
+        Some(Statement::If(if_statement)) => {
+            always_returns(&if_statement.if_true)
+                && if_statement
+                    .else_part
+                    .as_ref()
+                    .is_some_and(|else_part| always_returns(else_part))
+        }
+        // every case runs on into the last arm unless it breaks out
+        Some(Statement::Switch(switch_statement)) => {
+            switch_statement.arms.iter().any(|arm| arm.label.is_none())
+                && switch_statement
+                    .arms
+                    .last()
+                    .is_some_and(|arm| always_returns(&arm.body))
+                && !switch_statement
+                    .arms
+                    .iter()
+                    .any(|arm| breaks_out(&arm.body))
+        }
+        _ => false,
+    }
+}
+
+/// Whether `statements` contain a `break` for the statement around them, breaks in nested
+/// loops and switches stay inside those
+fn breaks_out(statements: &[Statement]) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::Break => true,
+        Statement::If(if_statement) => {
+            breaks_out(&if_statement.if_true)
+                || if_statement
+                    .else_part
+                    .as_ref()
+                    .is_some_and(|else_part| breaks_out(else_part))
+        }
+        _ => false,
+    })
+}
+
+struct Checker<'a> {
+    index: &'a ProgramIndex,
+    class: &'a Class,
+    typing: Typing,
+    class_vars: HashMap<String, (Type, DeclareType)>,
+    /// Constants and enum values declared so far, enum values by their `Enum.VALUE` name
+    constants: HashMap<String, Type>,
+    constant_values: HashMap<String, i16>,
+    locals: HashMap<String, (Type, DeclareType)>,
+    subroutine: Option<&'a SubroutineDec>,
+    errors: Vec<CompileError>,
+    dependencies: BTreeSet<String>,
+}
+
+impl<'a> Checker<'a> {
+    fn error(&mut self, message: String, span: Span) {
+        self.errors.push(CompileError::new(message, span));
+    }
+
+    /// Looks up another class, remembering the dependency
+    fn class_info(&mut self, class_name: &str) -> Option<&'a ClassInfo> {
+        if class_name != self.class.name {
+            self.dependencies.insert(class_name.to_string());
+        }
+        self.index.classes.get(class_name)
+    }
+
+    fn check_type_exists(&mut self, type_type: &Type, span: Span) {
+        if let Type::ClassName(name) = type_type {
+            if self.class_info(name).is_none() {
+                self.error(format!("unknown type {}", name), span);
+            }
+        }
+    }
+
+    fn class(&mut self) {
+        let class = self.class;
+        for var_dec in &class.var_dec {
+            self.check_type_exists(&var_dec.type_type, var_dec.span);
+            for name in &var_dec.variable_names {
+                if self.class_vars.contains_key(name) {
+                    self.error(
+                        format!("variable {} is declared more than once", name),
+                        var_dec.span,
+                    );
+                }
+                self.class_vars.insert(
+                    name.clone(),
+                    (var_dec.type_type.clone(), var_dec.declare_type),
+                );
+            }
+        }
+
+        for (idx, enum_dec) in class.enum_dec.iter().enumerate() {
+            if class.enum_dec[..idx]
+                .iter()
+                .any(|other| other.name == enum_dec.name)
+            {
+                self.error(
+                    format!("enum {} is declared more than once", enum_dec.name),
+                    enum_dec.span,
+                );
+            }
+            for name in &enum_dec.values {
+                let full_name = format!("{}.{}", enum_dec.name, name);
+                if self
+                    .constants
+                    .insert(full_name.clone(), Type::Int)
+                    .is_some()
+                {
+                    self.error(
+                        format!("{} is declared more than once", full_name),
+                        enum_dec.span,
+                    );
+                }
+            }
+        }
+        for const_dec in &class.const_dec {
+            self.const_dec(const_dec);
+        }
+
+        for sub in &class.sub_dec {
+            self.subroutine_dec(sub);
+        }
+    }
+
+    fn const_dec(&mut self, const_dec: &ConstDec) {
+        let name = &const_dec.name;
+        if !matches!(const_dec.type_type, Type::Int | Type::Char | Type::Boolean) {
+            self.error(
+                format!(
+                    "constant {} is of type {}, constants can only be int, char or boolean",
+                    name,
+                    type_name(&const_dec.type_type)
+                ),
+                const_dec.span,
+            );
+        }
+        if self.class_vars.contains_key(name) || self.constants.contains_key(name) {
+            self.error(
+                format!("{} is declared more than once", name),
+                const_dec.span,
+            );
+        }
+
+        let errors_before = self.errors.len();
+        let value = self.expression(&const_dec.value);
+        // a value with errors of its own is not reported a second time
+        if self.errors.len() == errors_before {
+            if !self.constant_values.contains_key(name) {
+                self.error(
+                    format!(
+                        "the value of constant {} has to be known at compile time",
+                        name
+                    ),
+                    const_dec.value.span,
+                );
+            } else if !self.assignable(&const_dec.type_type, &value, &const_dec.value) {
+                self.error(
+                    format!(
+                        "cannot assign {} to constant {} of type {}",
+                        describe(&value),
+                        name,
+                        type_name(&const_dec.type_type)
+                    ),
+                    const_dec.value.span,
+                );
+            }
+        }
+        self.constants
+            .insert(name.clone(), const_dec.type_type.clone());
+    }
+
+    fn subroutine_dec(&mut self, sub: &'a SubroutineDec) {
+        self.subroutine = Some(sub);
+        self.locals = HashMap::new();
+
+        self.check_type_exists(&sub.return_type, sub.span);
+        if let SubroutineType::Constructor = sub.subroutine_type {
+            if sub.return_type != Type::ClassName(self.class.name.clone()) {
+                self.error(
+                    format!(
+                        "constructor {} must return {}, not {}",
+                        sub.name,
+                        self.class.name,
+                        type_name(&sub.return_type)
+                    ),
+                    sub.span,
+                );
+            }
+        }
+
+        for (type_type, name) in &sub.parameter_list.list {
+            self.check_type_exists(type_type, sub.parameter_list.span);
+            self.declare_local(name, type_type, DeclareType::Arg, sub.parameter_list.span);
+        }
+        for var_dec in &sub.body.variable_declaration {
+            self.check_type_exists(&var_dec.type_type, var_dec.span);
+            for name in &var_dec.variable_names {
+                self.declare_local(name, &var_dec.type_type, DeclareType::Var, var_dec.span);
+            }
+        }
+
+        self.statements(&sub.body.body);
+
+        // an empty body is a stub still to be written
+        if !sub.body.body.is_empty() && !always_returns(&sub.body.body) {
+            self.error(
+                format!("subroutine {} can reach its end without a return", sub.name),
+                sub.span,
+            );
+        }
+    }
+
+    fn declare_local(&mut self, name: &str, type_type: &Type, kind: DeclareType, span: Span) {
+        if self.locals.contains_key(name) {
+            self.error(
+                format!("variable {} is declared more than once", name),
+                span,
+            );
+        }
+        self.locals
+            .insert(name.to_string(), (type_type.clone(), kind));
+    }
+
+    fn in_function(&self) -> bool {
+        matches!(
+            self.subroutine.map(|sub| sub.subroutine_type),
+            Some(SubroutineType::Function)
+        )
+    }
+
+    /// Whether `name`, or the `obj` of `obj.field`, is a variable. Variables hide the
+    /// constants and enums of the same name
+    fn is_variable(&self, name: &str) -> bool {
+        let variable = variable_part(name);
+        self.locals.contains_key(variable) || self.class_vars.contains_key(variable)
+    }
+
+    /// Type of a variable, reports unknown names and fields used in functions
+    fn lookup(&mut self, name: &str, span: Span) -> Option<Type> {
+        if let Some((object, field)) = name.split_once('.') {
+            if self.is_variable(object) {
+                return self.field(object, field, span);
+            }
+        }
+        if let Some((type_type, _)) = self.locals.get(name) {
+            return Some(type_type.clone());
+        }
+        match self.class_vars.get(name).cloned() {
+            Some((type_type, DeclareType::Field)) if self.in_function() => {
+                self.error(format!("field {} cannot be used in a function", name), span);
+                Some(type_type)
+            }
+            Some((type_type, _)) => Some(type_type),
+            None => match self.constants.get(name) {
+                Some(type_type) => Some(type_type.clone()),
+                None if name.contains('.') => {
+                    self.error(
+                        format!("cannot find enum value {} in this class", name),
+                        span,
+                    );
+                    None
+                }
+                None => {
+                    self.error(format!("cannot find variable {} in this scope", name), span);
+                    None
+                }
+            },
+        }
+    }
+
+    /// Type of `object.field`, a field of another object reached through its accessors
+    fn field(&mut self, object: &str, field: &str, span: Span) -> Option<Type> {
+        let class_name = match self.lookup(object, span)? {
+            Type::ClassName(class_name) => class_name,
+            other => {
+                self.error(
+                    format!(
+                        "{} is of type {} and has no fields",
+                        object,
+                        type_name(&other)
+                    ),
+                    span,
+                );
+                return None;
+            }
+        };
+        // an unknown variable type was already reported at its declaration
+        let info = self.class_info(&class_name)?;
+        match info.fields.iter().find(|(name, _)| name == field) {
+            Some((_, type_type)) => Some(type_type.clone()),
+            None => {
+                self.error(
+                    format!(
+                        "class {} has no field {} with accessors, only classes compiled with strict typing have them",
+                        class_name, field
+                    ),
+                    span,
+                );
+                None
+            }
+        }
+    }
+
+    /// Value of a constant expression, variables hide constants of the same name
+    fn resolved_value(&self, expression: &Expression) -> Option<i16> {
+        resolved_value(expression, &|name| {
+            if self.is_variable(name) {
+                None
+            } else {
+                self.constant_values.get(name).copied()
+            }
+        })
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    /// Whether `value`, computed by `expression`, may be stored where `expected` is declared.
+    /// Strict typing lets constants like 65 stand for a `char`
+    fn assignable(&self, expected: &Type, value: &Value, expression: &Expression) -> bool {
+        match self.typing {
+            Typing::Loose => compatible(expected, value),
+            Typing::Strict => {
+                strictly_compatible(expected, value)
+                    || (*expected == Type::Char
+                        && *value == Value::Known(Type::Int)
+                        && self.resolved_value(expression).is_some())
+            }
+        }
+    }
+
+    /// Whether arithmetic may use `value`
+    fn is_number(&self, value: &Value) -> bool {
+        match self.typing {
+            Typing::Loose => compatible(&Type::Int, value),
+            Typing::Strict => numeric(value),
+        }
+    }
+
+    /// A let statement, also the init and step of a for loop
+    fn assignment(&mut self, let_statement: &Let) {
+        if !self.is_variable(&let_statement.var_name)
+            && self.constants.contains_key(&let_statement.var_name)
+        {
+            self.error(
+                format!("cannot assign to constant {}", let_statement.var_name),
+                let_statement.span,
+            );
+        }
+        let var_type = self.lookup(&let_statement.var_name, let_statement.span);
+        let value = self.expression(&let_statement.bind_to);
+
+        if let Some(op) = &let_statement.compound {
+            // `x += y` is `x = x + y`, so both sides have to be numbers
+            if !self.is_number(&value) {
+                self.error(
+                    format!("operator {} cannot be used on {}", op, describe(&value)),
+                    let_statement.bind_to.span,
+                );
+            }
+            if let (true, Some(var_type)) = (let_statement.indices.is_empty(), &var_type) {
+                if !self.is_number(&Value::Known(var_type.clone())) {
+                    self.error(
+                        format!(
+                            "operator {} cannot be used on {} of type {}",
+                            op,
+                            let_statement.var_name,
+                            type_name(var_type)
+                        ),
+                        let_statement.span,
+                    );
+                }
+            }
+        }
+
+        if !let_statement.indices.is_empty() {
+            if let Some(var_type) = &var_type {
+                self.check_indexable(&let_statement.var_name, var_type, let_statement.span);
+            }
+            for index in &let_statement.indices {
+                self.check_numeric(index);
+            }
+        } else if let (Some(var_type), None) = (var_type, &let_statement.compound) {
+            if !self.assignable(&var_type, &value, &let_statement.bind_to) {
+                self.error(
+                    format!(
+                        "cannot assign {} to {} of type {}",
+                        describe(&value),
+                        let_statement.var_name,
+                        type_name(&var_type)
+                    ),
+                    let_statement.bind_to.span,
+                );
+            }
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let(let_statement) => self.assignment(let_statement),
+            Statement::If(if_statement) => {
+                self.condition(&if_statement.condition);
+                self.statements(&if_statement.if_true);
+                if let Some(else_part) = &if_statement.else_part {
+                    self.statements(else_part);
+                }
+            }
+            Statement::While(while_statement) => {
+                self.condition(&while_statement.condition);
+                self.statements(&while_statement.if_true);
+            }
+            Statement::For(for_statement) => {
+                if let Some(init) = &for_statement.init {
+                    self.assignment(init);
+                }
+                if let Some(condition) = &for_statement.condition {
+                    self.condition(condition);
+                }
+                if let Some(step) = &for_statement.step {
+                    self.assignment(step);
+                }
+                self.statements(&for_statement.body);
+            }
+            Statement::Break | Statement::Continue => {}
+            Statement::Switch(switch_statement) => {
+                self.check_numeric(&switch_statement.value);
+                let mut seen = vec![];
+                for arm in &switch_statement.arms {
+                    if let Some(label) = &arm.label {
+                        match self.resolved_value(label) {
+                            Some(value) if seen.contains(&value) => self.error(
+                                format!("case {} appears more than once", value),
+                                label.span,
+                            ),
+                            Some(value) => seen.push(value),
+                            None => self
+                                .error("case values have to be constants".to_string(), label.span),
+                        }
+                    }
+                    self.statements(&arm.body);
+                }
+            }
+            Statement::Do(do_statement) => {
+                self.call(&do_statement.call);
+            }
+            Statement::Return(return_statement) => {
+                let sub = self
+                    .subroutine
+                    .expect("statements are only checked in subroutines");
+                match (&return_statement.value, &sub.return_type) {
+                    (None, Type::Void) => {}
+                    (None, return_type) => self.error(
+                        format!(
+                            "{} must return a value of type {}",
+                            sub.name,
+                            type_name(return_type)
+                        ),
+                        return_statement.span,
+                    ),
+                    (Some(value), Type::Void) => {
+                        self.expression(value);
+                        self.error(
+                            format!("{} is void and cannot return a value", sub.name),
+                            value.span,
+                        );
+                    }
+                    (Some(value), return_type) => {
+                        let actual = self.expression(value);
+                        if !self.assignable(return_type, &actual, value) {
+                            self.error(
+                                format!(
+                                    "{} returns {}, found {}",
+                                    sub.name,
+                                    type_name(return_type),
+                                    describe(&actual)
+                                ),
+                                value.span,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// `name[...]` needs an object or a plain int holding an address, an Array with strict
+    /// typing
+    fn check_indexable(&mut self, name: &str, var_type: &Type, span: Span) {
+        let array = Type::ClassName("Array".to_string());
+        let indexable = match self.typing {
+            Typing::Loose => compatible(&array, &Value::Known(var_type.clone())),
+            Typing::Strict => *var_type == array,
+        };
+        if !indexable {
+            self.error(
+                format!(
+                    "{} is of type {} and cannot be indexed",
+                    name,
+                    type_name(var_type)
+                ),
+                span,
+            );
+        }
+    }
+
+    fn check_numeric(&mut self, expression: &Expression) {
+        let value = self.expression(expression);
+        if !self.is_number(&value) {
+            self.error(
+                format!("expected an int, found {}", describe(&value)),
+                expression.span,
+            );
+        }
+    }
+
+    /// The condition of an if or a loop, it has to be a boolean with strict typing
+    fn condition(&mut self, expression: &Expression) {
+        let value = self.expression(expression);
+        if self.typing == Typing::Strict && !boolean(&value) {
+            self.error(
+                format!(
+                    "a condition has to be a boolean, found {}",
+                    describe(&value)
+                ),
+                expression.span,
+            );
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) -> Value {
+        let mut value = self.term(&expression.intial_term);
+
+        for (op, term) in &expression.afterwards {
+            let right = self.term(term);
+            match self.typing {
+                Typing::Loose => {
+                    for operand in [&value, &right] {
+                        if op != "=" && !compatible(&Type::Int, operand) {
+                            self.error(
+                                format!("operator {} cannot be used on {}", op, describe(operand)),
+                                expression.span,
+                            );
+                        }
+                    }
+                }
+                Typing::Strict => {
+                    if !strict_operands(op, &value, &right) {
+                        self.error(
+                            format!(
+                                "operator {} cannot be used on {} and {}",
+                                op,
+                                describe(&value),
+                                describe(&right)
+                            ),
+                            expression.span,
+                        );
+                    }
+                }
+            }
+            let one_boolean = [&value, &right].contains(&&Value::Known(Type::Boolean));
+            value = match op.as_str() {
+                "<" | ">" | "=" => Value::Known(Type::Boolean),
+                "&" | "|"
+                    if value == Value::Known(Type::Boolean)
+                        && right == Value::Known(Type::Boolean) =>
+                {
+                    Value::Known(Type::Boolean)
+                }
+                // with strict typing `flags[i] & done` can only be a boolean
+                "&" | "|"
+                    if self.typing == Typing::Strict
+                        && one_boolean
+                        && boolean(&value)
+                        && boolean(&right) =>
+                {
+                    Value::Known(Type::Boolean)
+                }
+                _ => Value::Known(Type::Int),
+            };
+        }
+
+        value
+    }
+
+    fn term(&mut self, term: &Term) -> Value {
+        match term {
+            Term::IntegerConstant(_) => Value::Known(Type::Int),
+            Term::CharConstant(_) => Value::Known(Type::Char),
+            Term::StringConstant(_) => Value::Known(Type::ClassName("String".to_string())),
+            Term::KeywordConstant(keyword) => match keyword.as_str() {
+                "true" | "false" => Value::Known(Type::Boolean),
+                "null" => Value::Null,
+                _ => {
+                    if self.in_function() {
+                        let sub = self
+                            .subroutine
+                            .expect("terms are only checked in subroutines");
+                        self.error(
+                            format!("this cannot be used in function {}", sub.name),
+                            sub.span,
+                        );
+                    }
+                    Value::Known(Type::ClassName(self.class.name.clone()))
+                }
+            },
+            Term::VarName(name, span) => match self.lookup(name, *span) {
+                Some(type_type) => Value::Known(type_type),
+                None => Value::Unknown,
+            },
+            Term::ArrayAccess {
+                array_name,
+                indices,
+                span,
+            } => {
+                if let Some(var_type) = self.lookup(array_name, *span) {
+                    self.check_indexable(array_name, &var_type, *span);
+                }
+                for index in indices {
+                    self.check_numeric(index);
+                }
+                // array elements are untyped, `a[i][j]` indexes whatever `a[i]` holds
+                Value::Unknown
+            }
+            Term::ArrayLiteral { elements, .. } => {
+                for element in elements {
+                    self.expression(element);
+                }
+                Value::Known(Type::ClassName("Array".to_string()))
+            }
+            Term::SubroutineCall(call) => match self.call(call) {
+                Value::Known(Type::Void) => {
+                    self.error(
+                        format!("{} is void and has no value", call.subroutine_name),
+                        call.span,
+                    );
+                    Value::Unknown
+                }
+                value => value,
+            },
+            Term::Expression(expression) => self.expression(expression),
+            Term::UnaryOp { op, term } => {
+                let value = self.term(term);
+                let allowed = match self.typing {
+                    Typing::Loose => compatible(&Type::Int, &value),
+                    Typing::Strict => numeric(&value) || (op == "~" && boolean(&value)),
+                };
+                if !allowed {
+                    self.error(
+                        format!("operator {} cannot be used on {}", op, describe(&value)),
+                        self.term_span(term),
+                    );
+                }
+                match value {
+                    Value::Known(Type::Boolean) if op == "~" => value,
+                    Value::Unknown if self.typing == Typing::Strict => value,
+                    _ => Value::Known(Type::Int),
+                }
+            }
+            Term::Store { .. } | Term::Load(_) => unreachable!("only created after checking"),
+        }
+    }
+
+    /// Best span for a term, the parser only keeps spans on names and calls
+    fn term_span(&self, term: &Term) -> Span {
+        match term {
+            Term::VarName(_, span)
+            | Term::ArrayAccess { span, .. }
+            | Term::ArrayLiteral { span, .. } => *span,
+            Term::SubroutineCall(call) => call.span,
+            Term::Expression(expression) => expression.span,
+            Term::UnaryOp { term, .. } => self.term_span(term),
+            _ => self.subroutine.map_or(self.class.span, |sub| sub.span),
+        }
+    }
+
+    /// Resolves the callee, checks the arguments and gives back the return type
+    fn call(&mut self, call: &SubroutineCall) -> Value {
+        let arguments: Vec<Value> = call
+            .arguments
+            .list
+            .iter()
+            .map(|argument| self.expression(argument))
+            .collect();
+
+        // (class of the callee, whether there is an object to call a method on)
+        let (class_name, has_object) = match &call.obj_name {
+            None => {
+                let has_object = !self.in_function();
+                (self.class.name.clone(), has_object)
+            }
+            Some(obj_name)
+                if self.locals.contains_key(obj_name) || self.class_vars.contains_key(obj_name) =>
+            {
+                match self.lookup(obj_name, call.span) {
+                    Some(Type::ClassName(class_name)) => (class_name, true),
+                    Some(other) => {
+                        self.error(
+                            format!(
+                                "{} is of type {} and has no methods",
+                                obj_name,
+                                type_name(&other)
+                            ),
+                            call.span,
+                        );
+                        return Value::Unknown;
+                    }
+                    None => return Value::Unknown,
+                }
+            }
+            Some(class_name) => (class_name.clone(), false),
+        };
+
+        let Some(class_info) = self.class_info(&class_name) else {
+            // an unknown variable type was already reported at its declaration
+            if call.obj_name.as_ref() == Some(&class_name) {
+                self.error(
+                    format!("cannot find class or variable {}", class_name),
+                    call.span,
+                );
+            }
+            return Value::Unknown;
+        };
+        let Some(info) = class_info.subroutines.get(&call.subroutine_name) else {
+            self.error(
+                format!(
+                    "class {} has no subroutine {}",
+                    class_name, call.subroutine_name
+                ),
+                call.span,
+            );
+            return Value::Unknown;
+        };
+        let full_name = format!("{}.{}", class_name, call.subroutine_name);
+
+        match info.kind {
+            SubroutineType::Method if !has_object => {
+                let message = if call.obj_name.is_none() {
+                    format!(
+                        "method {} cannot be called from a function without an object",
+                        full_name
+                    )
+                } else {
+                    format!("method {} needs an object to be called on", full_name)
+                };
+                self.error(message, call.span);
+            }
+            SubroutineType::Function | SubroutineType::Constructor
+                if has_object && call.obj_name.is_some() =>
+            {
+                self.error(
+                    format!(
+                        "{} is a {} and must be called as {}",
+                        full_name,
+                        kind_name(info.kind),
+                        full_name
+                    ),
+                    call.span,
+                );
+            }
+            _ => {}
+        }
+
+        if info.params.len() != arguments.len() {
+            self.error(
+                format!(
+                    "{} takes {} argument(s), found {}",
+                    full_name,
+                    info.params.len(),
+                    arguments.len()
+                ),
+                call.span,
+            );
+        } else {
+            for (idx, (param, argument)) in info.params.iter().zip(&arguments).enumerate() {
+                if !self.assignable(param, argument, &call.arguments.list[idx]) {
+                    self.error(
+                        format!(
+                            "argument {} of {} must be {}, found {}",
+                            idx + 1,
+                            full_name,
+                            type_name(param),
+                            describe(argument)
+                        ),
+                        call.arguments.list[idx].span,
+                    );
+                }
+            }
+        }
+
+        Value::Known(info.return_type.clone())
+    }
+}
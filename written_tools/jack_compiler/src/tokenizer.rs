@@ -1,3 +1,5 @@
+use crate::error::CompileError;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TokenType {
     Keyword,
@@ -5,11 +7,39 @@ pub enum TokenType {
     Identifier,
     IntegerConstant,
     StringConstant,
+    /// `'a'` in the extended dialect
+    CharConstant,
+    /// Returned by [`Tokenizer::current`] once all tokens are used up, never stored
+    EndOfFile,
+}
+
+/// Position of a token in the source file, `line` and `col` are 1-based, `len` is in chars
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub col: usize,
+    pub len: usize,
+}
+
+impl Span {
+    /// Span from the start of `self` to the end of `end`, only `self` if they are on different lines
+    pub fn to(self, end: Span) -> Span {
+        if end.line == self.line && end.col >= self.col {
+            Span {
+                len: end.col + end.len - self.col,
+                ..self
+            }
+        } else {
+            self
+        }
+    }
 }
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Token {
     pub t_type: TokenType,
     pub string_repr: String,
+    pub span: Span,
 }
 // #[derive(Debug,Clone)]
 // enum KeywordType{
@@ -36,73 +66,56 @@ pub struct Token {
 //     This,
 // }
 
-impl Token {
-    pub fn from_string(input_string: String) -> Token {
-        let symbol_list = vec![
-            "{", "}", "(", ")", "[", "]", ".", ",", ";", "+", "-", "*", "/", "&", "|", "<", ">",
-            "=", "~",
-        ];
+const SYMBOLS: &[char] = &[
+    '{', '}', '(', ')', '[', ']', '.', ',', ';', '+', '-', '*', '/', '&', '|', '<', '>', '=', '~',
+];
 
-        let keyword_list = vec![
-            "class",
-            "constructor",
-            "function",
-            "method",
-            "field",
-            "static",
-            "var",
-            "int",
-            "char",
-            "boolean",
-            "void",
-            "true",
-            "false",
-            "null",
-            "this",
-            "let",
-            "do",
-            "if",
-            "else",
-            "while",
-            "return",
-        ];
+const KEYWORDS: &[&str] = &[
+    "class",
+    "constructor",
+    "function",
+    "method",
+    "field",
+    "static",
+    "var",
+    "int",
+    "char",
+    "boolean",
+    "void",
+    "true",
+    "false",
+    "null",
+    "this",
+    "let",
+    "do",
+    "if",
+    "else",
+    "while",
+    "return",
+];
 
-        if symbol_list.contains(&input_string.as_str()) {
-            Token {
-                t_type: TokenType::Symbol,
-                string_repr: input_string,
-            }
-        } else if input_string.chars().all(|ch| ch.is_ascii_digit()) && !input_string.is_empty() {
-            let number = input_string.parse::<u64>().unwrap();
-            if number > 32767 {
-                panic!("Integer Bigger than 32767 found!: {:?}", number);
-            }
-            Token {
-                t_type: TokenType::IntegerConstant,
-                string_repr: input_string,
-            }
-        } else if keyword_list.contains(&input_string.as_str()) {
-            Token {
-                t_type: TokenType::Keyword,
-                string_repr: input_string,
-            }
-        } else if input_string.starts_with('"') && input_string.ends_with('"') {
-            Token {
-                t_type: TokenType::StringConstant,
-                string_repr: input_string
-                    .strip_prefix('"')
-                    .unwrap()
-                    .strip_suffix('"')
-                    .unwrap()
-                    .to_string(),
-            }
-        } else {
-            Token {
-                t_type: TokenType::Identifier,
-                string_repr: input_string,
-            }
-        }
-    }
+/// Keywords of the extended dialect, plain identifiers in standard Jack
+const EXTENDED_KEYWORDS: &[&str] = &[
+    "for", "break", "continue", "switch", "case", "default", "const", "enum",
+];
+
+/// Symbols only the extended dialect knows, for `case 1:`
+const EXTENDED_SYMBOLS: &[char] = &[':'];
+
+/// Which flavour of Jack is compiled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /// The language of the Nand2Tetris book
+    #[default]
+    Standard,
+    /// Standard Jack plus `for`, `break`, `continue`, `else if`, `switch`, `+=`, `-=`, `++`,
+    /// `--` in let statements, `'a'` char constants, `0x1F` and `0b1010` integers, `\n`,
+    /// `\"`, `\'`, `\\` escapes and class level `const` and `enum`, still compiled to
+    /// standard VM code
+    Extended,
+}
+
+impl Token {
     pub fn to_xml_string(&self) -> String {
         match self.t_type {
             TokenType::Keyword => {
@@ -128,6 +141,22 @@ impl Token {
             TokenType::StringConstant => {
                 format!("<stringConstant> {} </stringConstant>", self.string_repr)
             }
+            TokenType::CharConstant => {
+                format!("<charConstant> {} </charConstant>", self.string_repr)
+            }
+            TokenType::EndOfFile => unreachable!("end of file is not a real token"),
+        }
+    }
+    /// Short description for error messages, like "symbol `;`"
+    pub fn describe(&self) -> String {
+        match self.t_type {
+            TokenType::Keyword => format!("keyword `{}`", self.string_repr),
+            TokenType::Symbol => format!("symbol `{}`", self.string_repr),
+            TokenType::Identifier => format!("identifier `{}`", self.string_repr),
+            TokenType::IntegerConstant => format!("integer `{}`", self.string_repr),
+            TokenType::StringConstant => format!("string \"{}\"", self.string_repr),
+            TokenType::CharConstant => format!("character '{}'", self.string_repr),
+            TokenType::EndOfFile => "end of file".to_string(),
         }
     }
 }
@@ -136,111 +165,47 @@ impl Token {
 pub struct Tokenizer {
     tokens: Vec<Token>,
     pos_idx: usize,
+    /// Position right after the last token, used for errors at the end of the file
+    end: Span,
 }
 
 impl Tokenizer {
-    pub fn from_string(input_string: String) -> Tokenizer {
-        // Preproccess and remove Comments first
-
-        let mut input_string = input_string
-            .lines()
-            .filter(|line| !line.starts_with("//"))
-            .filter(|line| !(line.starts_with("//") || line.is_empty()))
-            .map(|line| {
-                if line.contains("//") {
-                    line.split_at(line.find("//").unwrap()).0.to_owned()
-                } else {
-                    line.to_string()
-                }
-            })
-            .collect::<Vec<String>>();
-
-        let mut in_multiline_comment = false;
-
-        for line in &mut input_string {
-            if line.contains("/**") {
-                if line.contains("*/") {
-                    let after_multiline_end = line.split_at(line.find("*/").unwrap() + 2).1;
-                    *line = after_multiline_end.to_string();
-                    in_multiline_comment = false;
-                } else {
-                    let before_multiline = line.split_at(line.find("/**").unwrap()).0;
-                    *line = before_multiline.to_string();
-                    in_multiline_comment = true;
-                }
-            }
-            if in_multiline_comment {
-                if line.contains("*/") {
-                    let after_multiline_end = line.split_at(line.find("*/").unwrap() + 2).1;
-                    *line = after_multiline_end.to_string();
-                    in_multiline_comment = false;
-                } else {
-                    *line = String::new();
-                }
-            }
-        }
-        let input_string = input_string.join(" ");
-
-        let symbol_list = vec![
-            '{', '}', '(', ')', '[', ']', '.', ',', ';', '+', '-', '*', '/', '&', '|', '<', '>',
-            '=', '~',
-        ];
-
-        let mut new_tokenizer: Tokenizer = Tokenizer {
+    /// Lexes a whole .jack file in one pass, collecting every lexical error on the way
+    pub fn from_string(
+        input_string: String,
+        dialect: Dialect,
+    ) -> Result<Tokenizer, Vec<CompileError>> {
+        let mut lexer = Lexer {
+            dialect,
+            chars: input_string.chars().collect(),
+            pos: 0,
+            line: 1,
+            col: 1,
             tokens: vec![],
-            pos_idx: 0,
+            errors: vec![],
         };
+        lexer.run();
 
-        let mut current_piece: String = "".to_owned();
-        let mut string_mode = false;
-
-        for character in input_string.chars() {
-            if character == '"' {
-                string_mode = !string_mode;
-                current_piece.push(character);
-                continue;
-            }
-            if string_mode {
-                current_piece.push(character);
-                continue;
-            }
-            if character.is_ascii_whitespace() {
-                if current_piece.is_empty() {
-                    continue;
-                }
-                let new_token = Token::from_string(current_piece.clone());
-                current_piece = String::new();
-                new_tokenizer.tokens.push(new_token);
-                continue;
-            }
-            if character.is_ascii_alphabetic() {
-                current_piece.push(character);
-                continue;
-            }
-            if character == '_' {
-                current_piece.push(character);
-                continue;
-            }
-            if character.is_digit(10) {
-                current_piece.push(character);
-                continue;
-            }
-            if symbol_list.contains(&character) {
-                if !current_piece.is_empty() {
-                    let before_symbol = Token::from_string(current_piece.clone());
-                    new_tokenizer.tokens.push(before_symbol);
-                }
-
-                current_piece = String::new();
-                let symbol = Token::from_string(character.to_string());
-                new_tokenizer.tokens.push(symbol);
-                continue;
-            }
-            panic!("unknown char in Input Stream: {:?}", character);
+        if !lexer.errors.is_empty() {
+            return Err(lexer.errors);
         }
-        // println!("From String: {:?}",new_tokenizer);
-
-        new_tokenizer
+        let end = match lexer.tokens.last() {
+            Some(last) => Span {
+                line: last.span.line,
+                col: last.span.col + last.span.len,
+                len: 1,
+            },
+            None => Span {
+                line: 1,
+                col: 1,
+                len: 1,
+            },
+        };
+        Ok(Tokenizer {
+            tokens: lexer.tokens,
+            pos_idx: 0,
+            end,
+        })
     }
     pub fn to_xml_string(&self) -> String {
         let mut buffer: Vec<String> = vec!["<tokens>".to_string()];
@@ -256,40 +221,467 @@ impl Tokenizer {
 
 impl Tokenizer {
     pub fn current(&self) -> Token {
-        self.tokens[self.pos_idx].clone()
+        match self.tokens.get(self.pos_idx) {
+            Some(token) => token.clone(),
+            None => Token {
+                t_type: TokenType::EndOfFile,
+                string_repr: String::new(),
+                span: self.end,
+            },
+        }
     }
-    pub fn next(&self) -> Token {
-        self.tokens[self.pos_idx + 1].clone()
+    /// The token after the current one
+    pub fn peek(&self) -> Token {
+        self.peek_at(1)
+    }
+    /// The token `offset` places after the current one
+    pub fn peek_at(&self, offset: usize) -> Token {
+        match self.tokens.get(self.pos_idx + offset) {
+            Some(token) => token.clone(),
+            None => Token {
+                t_type: TokenType::EndOfFile,
+                string_repr: String::new(),
+                span: self.end,
+            },
+        }
+    }
+    /// Span of the token consumed last
+    pub fn previous_span(&self) -> Span {
+        match self
+            .pos_idx
+            .checked_sub(1)
+            .and_then(|idx| self.tokens.get(idx))
+        {
+            Some(token) => token.span,
+            None => self.end,
+        }
     }
     pub fn advance(&mut self) -> Token {
         let temp = self.current();
-        self.pos_idx += 1;
+        if self.pos_idx < self.tokens.len() {
+            self.pos_idx += 1;
+        }
         temp
     }
-    pub fn expect(&mut self, expected_type: TokenType) -> Token {
+    /// Index of the current token, for [`Tokenizer::text`]
+    pub fn position(&self) -> usize {
+        self.pos_idx
+    }
+    /// The tokens from position `start` up to `end` written out again, with a space only
+    /// after commas and where two words would run together
+    pub fn text(&self, start: usize, end: usize) -> String {
+        let is_word = |token: &Token| token.t_type != TokenType::Symbol;
+        let mut buf = String::new();
+        let mut previous: Option<&Token> = None;
+        for token in &self.tokens[start..end.min(self.tokens.len())] {
+            if previous.is_some_and(|previous| {
+                (is_word(previous) && is_word(token)) || previous.string_repr == ","
+            }) {
+                buf.push(' ');
+            }
+            match token.t_type {
+                TokenType::StringConstant => buf.push_str(&format!("\"{}\"", token.string_repr)),
+                TokenType::CharConstant => buf.push_str(&format!("'{}'", token.string_repr)),
+                _ => buf.push_str(&token.string_repr),
+            }
+            previous = Some(token);
+        }
+        buf
+    }
+    pub fn is_at_end(&self) -> bool {
+        self.pos_idx >= self.tokens.len()
+    }
+    /// Checks the current token without consuming it
+    pub fn at(&self, expected_type: TokenType, expected_string: &str) -> bool {
+        let current = self.current();
+        current.t_type == expected_type && current.string_repr == expected_string
+    }
+    pub fn expect(&mut self, expected_type: TokenType) -> Result<Token, CompileError> {
         if self.current().t_type == expected_type {
-            self.advance()
+            Ok(self.advance())
         } else {
-            panic!(
-                "Expected Type: {:?},found: {:?}",
-                expected_type,
-                self.current()
-            );
+            Err(CompileError::new(
+                format!(
+                    "expected {}, found {}",
+                    describe_type(&expected_type),
+                    self.current().describe()
+                ),
+                self.current().span,
+            ))
         }
     }
-    pub fn expect_and_string(&mut self, expected_type: TokenType, expected_string: &str) -> Token {
-        if self.current().t_type == expected_type && self.current().string_repr == expected_string {
-            self.advance()
+    pub fn expect_and_string(
+        &mut self,
+        expected_type: TokenType,
+        expected_string: &str,
+    ) -> Result<Token, CompileError> {
+        if self.at(expected_type.clone(), expected_string) {
+            Ok(self.advance())
         } else {
-            panic!(
-                "Expected Type: {:?} and String: {:?},found: {:?}",
-                expected_type,
-                expected_string,
-                self.current()
-            );
+            let expected = Token {
+                t_type: expected_type,
+                string_repr: expected_string.to_string(),
+                span: Span::default(),
+            };
+            Err(CompileError::new(
+                format!(
+                    "expected {}, found {}",
+                    expected.describe(),
+                    self.current().describe()
+                ),
+                self.current().span,
+            ))
         }
     }
-    pub fn still_left(&self) -> bool {
-        self.pos_idx < self.tokens.len()
+}
+
+fn describe_type(t_type: &TokenType) -> &'static str {
+    match t_type {
+        TokenType::Keyword => "a keyword",
+        TokenType::Symbol => "a symbol",
+        TokenType::Identifier => "an identifier",
+        TokenType::IntegerConstant => "an integer",
+        TokenType::StringConstant => "a string",
+        TokenType::CharConstant => "a character",
+        TokenType::EndOfFile => "end of file",
+    }
+}
+
+/// Value of an integer constant as written, decimal or with a `0x` or `0b` prefix. `None` if
+/// it does not even fit a u64
+pub fn integer_value(written: &str) -> Option<u64> {
+    let (digits, radix) = match written.get(..2) {
+        Some("0x") | Some("0X") => (&written[2..], 16),
+        Some("0b") | Some("0B") => (&written[2..], 2),
+        _ => (written, 10),
+    };
+    u64::from_str_radix(digits, radix).ok()
+}
+
+/// Resolves the escapes of a string or char constant of the extended dialect, the error names
+/// the first unknown escape
+pub fn unescape(written: &str) -> Result<String, String> {
+    let mut value = String::new();
+    let mut chars = written.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            value.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some(escaped @ ('"' | '\'' | '\\')) => value.push(escaped),
+            Some(other) => {
+                return Err(format!(
+                    "unknown escape `\\{}`, expected \\n, \\\", \\' or \\\\",
+                    other
+                ))
+            }
+            None => return Err("`\\` at the end has nothing to escape".to_string()),
+        }
+    }
+    Ok(value)
+}
+
+/// Code of a decoded char in the Hack character set, which puts newline at 128
+pub fn char_code(ch: char) -> u16 {
+    match ch {
+        '\n' => 128,
+        _ => ch as u16,
+    }
+}
+
+/// A decoded string or char on one line again, for the XML of the parse tree
+pub fn one_line(value: &str) -> String {
+    value.replace('\n', "\\n")
+}
+
+/// Single pass over the chars of a file, keeping track of line and column
+struct Lexer {
+    dialect: Dialect,
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    col: usize,
+    tokens: Vec<Token>,
+    errors: Vec<CompileError>,
+}
+
+impl Lexer {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek(0)?;
+        self.pos += 1;
+        if ch == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(ch)
+    }
+    fn span_from(&self, line: usize, col: usize) -> Span {
+        Span {
+            line,
+            col,
+            len: if self.line == line { self.col - col } else { 1 },
+        }
+    }
+    fn push(&mut self, t_type: TokenType, string_repr: String, span: Span) {
+        self.tokens.push(Token {
+            t_type,
+            string_repr,
+            span,
+        });
+    }
+
+    fn run(&mut self) {
+        while let Some(ch) = self.peek(0) {
+            let (line, col) = (self.line, self.col);
+            match ch {
+                _ if ch.is_whitespace() => {
+                    self.bump();
+                }
+                '/' if self.peek(1) == Some('/') => {
+                    while !matches!(self.peek(0), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                // covers both `/* */` and `/** */`
+                '/' if self.peek(1) == Some('*') => {
+                    self.bump();
+                    self.bump();
+                    loop {
+                        match self.bump() {
+                            Some('*') if self.peek(0) == Some('/') => {
+                                self.bump();
+                                break;
+                            }
+                            Some(_) => {}
+                            None => {
+                                self.errors.push(CompileError::new(
+                                    "unterminated block comment",
+                                    Span { line, col, len: 2 },
+                                ));
+                                break;
+                            }
+                        }
+                    }
+                }
+                '"' => self.string(line, col),
+                '\'' if self.dialect == Dialect::Extended => self.char_constant(line, col),
+                '0' if self.dialect == Dialect::Extended
+                    && matches!(self.peek(1), Some('x' | 'X' | 'b' | 'B')) =>
+                {
+                    self.prefixed_integer(line, col)
+                }
+                _ if ch.is_ascii_digit() => self.integer(line, col),
+                _ if ch.is_ascii_alphabetic() || ch == '_' => {
+                    let mut word = String::new();
+                    while let Some(ch) = self.peek(0) {
+                        if !(ch.is_ascii_alphanumeric() || ch == '_') {
+                            break;
+                        }
+                        word.push(ch);
+                        self.bump();
+                    }
+                    let t_type = if KEYWORDS.contains(&word.as_str())
+                        || (self.dialect == Dialect::Extended
+                            && EXTENDED_KEYWORDS.contains(&word.as_str()))
+                    {
+                        TokenType::Keyword
+                    } else {
+                        TokenType::Identifier
+                    };
+                    let span = self.span_from(line, col);
+                    self.push(t_type, word, span);
+                }
+                _ if SYMBOLS.contains(&ch)
+                    || (self.dialect == Dialect::Extended && EXTENDED_SYMBOLS.contains(&ch)) =>
+                {
+                    self.bump();
+                    let span = self.span_from(line, col);
+                    self.push(TokenType::Symbol, ch.to_string(), span);
+                }
+                _ => {
+                    self.bump();
+                    let span = self.span_from(line, col);
+                    self.errors.push(CompileError::new(
+                        format!("unknown character {:?}", ch),
+                        span,
+                    ));
+                }
+            }
+        }
+    }
+
+    /// A string constant runs to the next `"` on the same line, comment markers inside it
+    /// are just text. The Hack character set only has printable ASCII. The extended dialect
+    /// also has escapes, the token keeps them as written
+    fn string(&mut self, line: usize, col: usize) {
+        self.bump();
+        let Some(string) = self.quoted('"', "string", line, col) else {
+            return;
+        };
+        let span = self.span_from(line, col);
+        let length = match self.decode(&string, span) {
+            Some(value) => value.chars().count(),
+            None => return,
+        };
+        // the length is pushed as a constant for String.new
+        if length > 32767 {
+            self.errors.push(CompileError::new(
+                "string constant is longer than the 32767 characters String.new can hold",
+                span,
+            ));
+            return;
+        }
+        self.push(TokenType::StringConstant, string, span);
+    }
+
+    /// `'a'` has to hold exactly one char once the escapes are resolved
+    fn char_constant(&mut self, line: usize, col: usize) {
+        self.bump();
+        let Some(written) = self.quoted('\'', "character", line, col) else {
+            return;
+        };
+        let span = self.span_from(line, col);
+        let Some(value) = self.decode(&written, span) else {
+            return;
+        };
+        let message = match value.chars().count() {
+            1 => {
+                self.push(TokenType::CharConstant, written, span);
+                return;
+            }
+            0 => "empty character constant".to_string(),
+            _ => format!(
+                "character constant '{}' holds more than one character, strings use \"",
+                written
+            ),
+        };
+        self.errors.push(CompileError::new(message, span));
+    }
+
+    /// Text up to the closing `quote` on the same line, with a backslash keeping the char
+    /// after it in the extended dialect. Only printable ASCII is allowed
+    fn quoted(&mut self, quote: char, what: &str, line: usize, col: usize) -> Option<String> {
+        let mut text = String::new();
+        loop {
+            match self.peek(0) {
+                Some(ch) if ch == quote => {
+                    self.bump();
+                    return Some(text);
+                }
+                Some('\n') | Some('\r') | None => {
+                    let span = self.span_from(line, col);
+                    self.errors.push(CompileError::new(
+                        format!("unterminated {} constant", what),
+                        span,
+                    ));
+                    return None;
+                }
+                Some(ch) => {
+                    let (ch_line, ch_col) = (self.line, self.col);
+                    self.bump();
+                    if !(' '..='~').contains(&ch) {
+                        let span = self.span_from(ch_line, ch_col);
+                        self.errors.push(CompileError::new(
+                            format!(
+                                "unsupported character {:?} in {} constant, only printable ASCII is allowed",
+                                ch, what
+                            ),
+                            span,
+                        ));
+                    }
+                    text.push(ch);
+                    if ch == '\\'
+                        && self.dialect == Dialect::Extended
+                        && matches!(self.peek(0), Some(next) if next != '\n' && next != '\r')
+                    {
+                        text.push(self.bump().expect("peeked before"));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Value of a string or char constant as written, standard Jack has no escapes
+    fn decode(&mut self, written: &str, span: Span) -> Option<String> {
+        if self.dialect == Dialect::Standard {
+            return Some(written.to_string());
+        }
+        match unescape(written) {
+            Ok(value) => Some(value),
+            Err(message) => {
+                self.errors.push(CompileError::new(message, span));
+                None
+            }
+        }
+    }
+
+    fn integer(&mut self, line: usize, col: usize) {
+        let mut digits = String::new();
+        while let Some(ch) = self.peek(0) {
+            if !ch.is_ascii_digit() {
+                break;
+            }
+            digits.push(ch);
+            self.bump();
+        }
+        let span = self.span_from(line, col);
+        self.integer_in_range(digits, span);
+    }
+
+    /// `0x1F` or `0b1010`, any letter or digit after the prefix belongs to the constant
+    fn prefixed_integer(&mut self, line: usize, col: usize) {
+        let mut written = String::new();
+        written.push(self.bump().expect("peeked before"));
+        written.push(self.bump().expect("peeked before"));
+        let (radix, name) = match written.as_str() {
+            "0x" | "0X" => (16, "hex"),
+            _ => (2, "binary"),
+        };
+        while let Some(ch) = self.peek(0) {
+            if !(ch.is_ascii_alphanumeric() || ch == '_') {
+                break;
+            }
+            written.push(ch);
+            self.bump();
+        }
+        let span = self.span_from(line, col);
+        let message = match written[2..].chars().find(|ch| !ch.is_digit(radix)) {
+            _ if written.len() == 2 => format!("expected {} digits after {}", name, written),
+            Some(ch) => format!("`{}` is not a {} digit in {}", ch, name, written),
+            None => {
+                self.integer_in_range(written, span);
+                return;
+            }
+        };
+        self.errors.push(CompileError::new(message, span));
+    }
+
+    /// The check happens on the value, so `0x8000` is as big as 32768
+    fn integer_in_range(&mut self, written: String, span: Span) {
+        // anything too long for a u64 is too big as well. 32768 is only valid after a unary
+        // minus, which the parser checks
+        match integer_value(&written) {
+            Some(number) if number <= 32768 => self.push(TokenType::IntegerConstant, written, span),
+            Some(number) if !written.chars().all(|ch| ch.is_ascii_digit()) => {
+                self.errors.push(CompileError::new(
+                    format!(
+                        "integer constant {} is {}, bigger than 32767",
+                        written, number
+                    ),
+                    span,
+                ))
+            }
+            _ => self.errors.push(CompileError::new(
+                format!("integer constant {} is bigger than 32767", written),
+                span,
+            )),
+        }
     }
 }
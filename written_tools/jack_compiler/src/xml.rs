@@ -0,0 +1,389 @@
+//! Project 10 style parse tree XML (`Main.xml`) written from the typed AST.
+//!
+//! The AST keeps everything the XML needs, so the tokens are reconstructed in source
+//! order instead of running a second parser.
+
+use crate::{
+    parser_non_xml::{
+        Class, DeclareType, Expression, Let, Statement, SubroutineCall, SubroutineType, Term, Type,
+    },
+    tokenizer::{one_line, Span, Token, TokenType},
+};
+
+pub fn class_to_xml(class: &Class) -> String {
+    let mut writer = XmlWriter {
+        buf: vec![],
+        depth: 0,
+    };
+    writer.class(class);
+    writer.buf.join("\n") + "\n"
+}
+
+struct XmlWriter {
+    buf: Vec<String>,
+    depth: usize,
+}
+
+impl XmlWriter {
+    fn line(&mut self, line: String) {
+        self.buf
+            .push(format!("{}{}", "  ".repeat(self.depth), line));
+    }
+    fn open(&mut self, tag: &str) {
+        self.line(format!("<{}>", tag));
+        self.depth += 1;
+    }
+    fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.line(format!("</{}>", tag));
+    }
+    fn token(&mut self, t_type: TokenType, string_repr: &str) {
+        let token = Token {
+            t_type,
+            string_repr: string_repr.to_string(),
+            span: Span::default(),
+        };
+        self.line(token.to_xml_string());
+    }
+    fn keyword(&mut self, keyword: &str) {
+        self.token(TokenType::Keyword, keyword);
+    }
+    fn symbol(&mut self, symbol: &str) {
+        self.token(TokenType::Symbol, symbol);
+    }
+    fn identifier(&mut self, identifier: &str) {
+        self.token(TokenType::Identifier, identifier);
+    }
+    fn type_name(&mut self, type_type: &Type) {
+        match type_type {
+            Type::Int => self.keyword("int"),
+            Type::Char => self.keyword("char"),
+            Type::Boolean => self.keyword("boolean"),
+            Type::Void => self.keyword("void"),
+            Type::ClassName(name) => self.identifier(name),
+        }
+    }
+    /// A variable, `Dir.UP` or `p.x`
+    fn name(&mut self, name: &str) {
+        match name.split_once('.') {
+            Some((first, second)) => {
+                self.identifier(first);
+                self.symbol(".");
+                self.identifier(second);
+            }
+            None => self.identifier(name),
+        }
+    }
+    /// `name, name, ...`
+    fn names(&mut self, names: &[String]) {
+        for (idx, name) in names.iter().enumerate() {
+            if idx > 0 {
+                self.symbol(",");
+            }
+            self.identifier(name);
+        }
+    }
+
+    fn class(&mut self, class: &Class) {
+        self.open("class");
+        self.keyword("class");
+        self.identifier(&class.name);
+        self.symbol("{");
+
+        for var_dec in &class.var_dec {
+            self.open("classVarDec");
+            self.keyword(match var_dec.declare_type {
+                DeclareType::Static => "static",
+                _ => "field",
+            });
+            self.type_name(&var_dec.type_type);
+            self.names(&var_dec.variable_names);
+            self.symbol(";");
+            self.close("classVarDec");
+        }
+        for const_dec in &class.const_dec {
+            self.open("constDec");
+            self.keyword("const");
+            self.type_name(&const_dec.type_type);
+            self.identifier(&const_dec.name);
+            self.symbol("=");
+            self.expression(&const_dec.value);
+            self.symbol(";");
+            self.close("constDec");
+        }
+        for enum_dec in &class.enum_dec {
+            self.open("enumDec");
+            self.keyword("enum");
+            self.identifier(&enum_dec.name);
+            self.symbol("{");
+            self.names(&enum_dec.values);
+            self.symbol("}");
+            self.close("enumDec");
+        }
+
+        for sub in &class.sub_dec {
+            self.open("subroutineDec");
+            self.keyword(match sub.subroutine_type {
+                SubroutineType::Constructor => "constructor",
+                SubroutineType::Function => "function",
+                SubroutineType::Method => "method",
+            });
+            self.type_name(&sub.return_type);
+            self.identifier(&sub.name);
+            self.symbol("(");
+            self.open("parameterList");
+            for (idx, (type_type, name)) in sub.parameter_list.list.iter().enumerate() {
+                if idx > 0 {
+                    self.symbol(",");
+                }
+                self.type_name(type_type);
+                self.identifier(name);
+            }
+            self.close("parameterList");
+            self.symbol(")");
+
+            self.open("subroutineBody");
+            self.symbol("{");
+            for var_dec in &sub.body.variable_declaration {
+                self.open("varDec");
+                self.keyword("var");
+                self.type_name(&var_dec.type_type);
+                self.names(&var_dec.variable_names);
+                self.symbol(";");
+                self.close("varDec");
+            }
+            self.statements(&sub.body.body);
+            self.symbol("}");
+            self.close("subroutineBody");
+            self.close("subroutineDec");
+        }
+
+        self.symbol("}");
+        self.close("class");
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        self.open("statements");
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.close("statements");
+    }
+
+    /// `{ statements }`
+    fn block(&mut self, statements: &[Statement]) {
+        self.symbol("{");
+        self.statements(statements);
+        self.symbol("}");
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Let(let_statement) => {
+                self.open("letStatement");
+                self.keyword("let");
+                self.assignment(let_statement);
+                self.symbol(";");
+                self.close("letStatement");
+            }
+            Statement::If(if_statement) => {
+                self.open("ifStatement");
+                self.keyword("if");
+                self.symbol("(");
+                self.expression(&if_statement.condition);
+                self.symbol(")");
+                self.block(&if_statement.if_true);
+                if let Some(else_part) = &if_statement.else_part {
+                    self.keyword("else");
+                    self.block(else_part);
+                }
+                self.close("ifStatement");
+            }
+            Statement::While(while_statement) => {
+                self.open("whileStatement");
+                self.keyword("while");
+                self.symbol("(");
+                self.expression(&while_statement.condition);
+                self.symbol(")");
+                self.block(&while_statement.if_true);
+                self.close("whileStatement");
+            }
+            Statement::Do(do_statement) => {
+                self.open("doStatement");
+                self.keyword("do");
+                self.subroutine_call(&do_statement.call);
+                self.symbol(";");
+                self.close("doStatement");
+            }
+            Statement::Return(return_statement) => {
+                self.open("returnStatement");
+                self.keyword("return");
+                if let Some(value) = &return_statement.value {
+                    self.expression(value);
+                }
+                self.symbol(";");
+                self.close("returnStatement");
+            }
+            Statement::For(for_statement) => {
+                self.open("forStatement");
+                self.keyword("for");
+                self.symbol("(");
+                if let Some(init) = &for_statement.init {
+                    self.open("assignment");
+                    self.assignment(init);
+                    self.close("assignment");
+                }
+                self.symbol(";");
+                if let Some(condition) = &for_statement.condition {
+                    self.expression(condition);
+                }
+                self.symbol(";");
+                if let Some(step) = &for_statement.step {
+                    self.open("assignment");
+                    self.assignment(step);
+                    self.close("assignment");
+                }
+                self.symbol(")");
+                self.block(&for_statement.body);
+                self.close("forStatement");
+            }
+            Statement::Switch(switch_statement) => {
+                self.open("switchStatement");
+                self.keyword("switch");
+                self.symbol("(");
+                self.expression(&switch_statement.value);
+                self.symbol(")");
+                self.symbol("{");
+                for arm in &switch_statement.arms {
+                    self.open("switchArm");
+                    match &arm.label {
+                        Some(label) => {
+                            self.keyword("case");
+                            self.expression(label);
+                        }
+                        None => self.keyword("default"),
+                    }
+                    self.symbol(":");
+                    self.statements(&arm.body);
+                    self.close("switchArm");
+                }
+                self.symbol("}");
+                self.close("switchStatement");
+            }
+            Statement::Break => {
+                self.open("breakStatement");
+                self.keyword("break");
+                self.symbol(";");
+                self.close("breakStatement");
+            }
+            Statement::Continue => {
+                self.open("continueStatement");
+                self.keyword("continue");
+                self.symbol(";");
+                self.close("continueStatement");
+            }
+        }
+    }
+
+    /// `varName ([expression])* = expression`, shared by let statements and for loops
+    fn assignment(&mut self, let_statement: &Let) {
+        self.name(&let_statement.var_name);
+        self.indices(&let_statement.indices);
+        match let_statement.compound.as_deref() {
+            // the 1 of `++` and `--` is not in the source
+            Some(op @ ("++" | "--")) => {
+                self.symbol(&op[..1]);
+                self.symbol(&op[1..]);
+            }
+            Some(op) => {
+                self.symbol(&op[..1]);
+                self.symbol("=");
+                self.expression(&let_statement.bind_to);
+            }
+            None => {
+                self.symbol("=");
+                self.expression(&let_statement.bind_to);
+            }
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        self.open("expression");
+        self.term(&expression.intial_term);
+        for (op, term) in &expression.afterwards {
+            self.symbol(op);
+            self.term(term);
+        }
+        self.close("expression");
+    }
+
+    fn term(&mut self, term: &Term) {
+        self.open("term");
+        match term {
+            Term::IntegerConstant(int) => self.token(TokenType::IntegerConstant, &int.to_string()),
+            Term::StringConstant(string) => {
+                self.token(TokenType::StringConstant, &one_line(string))
+            }
+            Term::CharConstant(character) => {
+                self.token(TokenType::CharConstant, &one_line(&character.to_string()))
+            }
+            Term::KeywordConstant(keyword) => self.keyword(keyword),
+            Term::VarName(name, _) => self.name(name),
+            Term::ArrayAccess {
+                array_name,
+                indices,
+                ..
+            } => {
+                self.name(array_name);
+                self.indices(indices);
+            }
+            Term::ArrayLiteral { elements, .. } => {
+                self.symbol("[");
+                self.expression_list(elements);
+                self.symbol("]");
+            }
+            Term::SubroutineCall(call) => self.subroutine_call(call),
+            Term::Expression(expression) => {
+                self.symbol("(");
+                self.expression(expression);
+                self.symbol(")");
+            }
+            Term::UnaryOp { op, term } => {
+                self.symbol(op);
+                self.term(term);
+            }
+            Term::Store { .. } | Term::Load(_) => unreachable!("only created by the optimizer"),
+        }
+        self.close("term");
+    }
+
+    fn subroutine_call(&mut self, call: &SubroutineCall) {
+        if let Some(obj_name) = &call.obj_name {
+            self.identifier(obj_name);
+            self.symbol(".");
+        }
+        self.identifier(&call.subroutine_name);
+        self.symbol("(");
+        self.expression_list(&call.arguments.list);
+        self.symbol(")");
+    }
+
+    fn indices(&mut self, indices: &[Expression]) {
+        for index in indices {
+            self.symbol("[");
+            self.expression(index);
+            self.symbol("]");
+        }
+    }
+
+    fn expression_list(&mut self, list: &[Expression]) {
+        self.open("expressionList");
+        for (idx, expression) in list.iter().enumerate() {
+            if idx > 0 {
+                self.symbol(",");
+            }
+            self.expression(expression);
+        }
+        self.close("expressionList");
+    }
+}
@@ -0,0 +1,64 @@
+//! Every folder in tests/diagnostics is a program with mistakes. Its .jack files are
+//! compiled together (a jack.config in the folder applies) and the compiler has to fail
+//! with exactly the output in `expected.txt` on stderr.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+fn sorted_entries(dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    entries
+}
+
+#[test]
+fn diagnostics_of_bad_programs() {
+    let cases = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/diagnostics");
+    let mut failures = vec![];
+
+    for case in sorted_entries(&cases) {
+        let name = case.file_name().unwrap().to_str().unwrap().to_string();
+        // compile a copy so that no output lands in the source tree
+        let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+            .join("diagnostics")
+            .join(&name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut jack_files = vec![];
+        for file in sorted_entries(&case) {
+            let file_name = file.file_name().unwrap().to_str().unwrap().to_string();
+            if file_name == "expected.txt" {
+                continue;
+            }
+            fs::copy(&file, dir.join(&file_name)).unwrap();
+            if file_name.ends_with(".jack") {
+                jack_files.push(file_name);
+            }
+        }
+
+        let output = Command::new(env!("CARGO_BIN_EXE_jack_compiler"))
+            .current_dir(&dir)
+            .args(&jack_files)
+            .args(["--emit", "vm"])
+            .output()
+            .unwrap();
+        let expected = fs::read_to_string(case.join("expected.txt")).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        if output.status.success() {
+            failures.push(format!("{}: compiled without errors", name));
+        } else if stderr != expected {
+            failures.push(format!(
+                "{}: expected\n{}\nfound\n{}",
+                name, expected, stderr
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
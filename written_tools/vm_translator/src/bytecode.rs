@@ -0,0 +1,304 @@
+//! Compact binary encoding of one .vm file (`.vmb`).
+//!
+//! All numbers are little endian.
+//!
+//! ```text
+//! magic        "HVMB"
+//! version      u8
+//! symbols      u16 count, then per symbol: u16 byte length + UTF-8 bytes
+//! constants    u16 count, then u16 values
+//! instructions u32 count, then per instruction: opcode u8 + operands
+//! ```
+//!
+//! | opcode      | instruction | operands                                   |
+//! |-------------|-------------|--------------------------------------------|
+//! | 0x00..=0x08 | arithmetic  | none, in the order of [`ArithOp::ALL`]     |
+//! | 0x10        | push        | segment u8, index u16 (constant table index for `constant`) |
+//! | 0x11        | pop         | segment u8, index u16                      |
+//! | 0x20        | label       | symbol u16                                 |
+//! | 0x21        | goto        | symbol u16                                 |
+//! | 0x22        | if-goto     | symbol u16                                 |
+//! | 0x30        | function    | symbol u16, nlocals u16                    |
+//! | 0x31        | call        | symbol u16, nargs u16                      |
+//! | 0x32        | return      | none                                       |
+//!
+//! Function and label names are interned in the symbol table, `push constant`
+//! values in the constant table. Source lines are not stored, commands read back
+//! get their instruction number (1-based) as line.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{ArithOp, Segment, VMCommand, VmInstr};
+
+const MAGIC: &[u8; 4] = b"HVMB";
+const VERSION: u8 = 1;
+
+const OP_PUSH: u8 = 0x10;
+const OP_POP: u8 = 0x11;
+const OP_LABEL: u8 = 0x20;
+const OP_GOTO: u8 = 0x21;
+const OP_IF_GOTO: u8 = 0x22;
+const OP_FUNCTION: u8 = 0x30;
+const OP_CALL: u8 = 0x31;
+const OP_RETURN: u8 = 0x32;
+
+const SEGMENTS: [Segment; 8] = [
+    Segment::Local,
+    Segment::Argument,
+    Segment::This,
+    Segment::That,
+    Segment::Static,
+    Segment::Temp,
+    Segment::Constant,
+    Segment::Pointer,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BytecodeError {
+    /// Byte offset into the input where decoding failed
+    pub offset: usize,
+    pub message: String,
+}
+impl fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid bytecode at byte {}: {}",
+            self.offset, self.message
+        )
+    }
+}
+impl std::error::Error for BytecodeError {}
+
+/// Commands that do not fit the limits of the format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteBytecodeError {
+    pub message: String,
+}
+impl fmt::Display for WriteBytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot write bytecode: {}", self.message)
+    }
+}
+impl std::error::Error for WriteBytecodeError {}
+
+fn too_many(what: &str) -> WriteBytecodeError {
+    WriteBytecodeError {
+        message: format!("more than {} {} in one file", u16::MAX, what),
+    }
+}
+
+/// Interns strings and constants while writing
+#[derive(Default)]
+struct Table<T> {
+    values: Vec<T>,
+    indices: HashMap<T, u16>,
+}
+impl<T: Clone + Eq + std::hash::Hash> Table<T> {
+    /// `None` once the table is full
+    fn intern(&mut self, value: &T) -> Option<u16> {
+        if let Some(idx) = self.indices.get(value) {
+            return Some(*idx);
+        }
+        let idx = u16::try_from(self.values.len()).ok()?;
+        self.values.push(value.clone());
+        self.indices.insert(value.clone(), idx);
+        Some(idx)
+    }
+}
+
+/// Encode the commands of one .vm file
+pub fn write_bytecode(commands: &[VMCommand]) -> Result<Vec<u8>, WriteBytecodeError> {
+    let mut symbols: Table<String> = Table::default();
+    let mut constants: Table<u16> = Table::default();
+    let mut code: Vec<u8> = vec![];
+
+    for command in commands {
+        match &command.instr {
+            VmInstr::Arith(op) => {
+                code.push(ArithOp::ALL.iter().position(|other| other == op).unwrap() as u8);
+            }
+            VmInstr::Push(segment, index) | VmInstr::Pop(segment, index) => {
+                code.push(if let VmInstr::Push(..) = command.instr {
+                    OP_PUSH
+                } else {
+                    OP_POP
+                });
+                code.push(SEGMENTS.iter().position(|other| other == segment).unwrap() as u8);
+                let operand = if *segment == Segment::Constant {
+                    constants
+                        .intern(index)
+                        .ok_or_else(|| too_many("constants"))?
+                } else {
+                    *index
+                };
+                code.extend(operand.to_le_bytes());
+            }
+            VmInstr::Label(label) | VmInstr::Goto(label) | VmInstr::IfGoto(label) => {
+                code.push(match command.instr {
+                    VmInstr::Label(_) => OP_LABEL,
+                    VmInstr::Goto(_) => OP_GOTO,
+                    _ => OP_IF_GOTO,
+                });
+                let symbol = symbols.intern(label).ok_or_else(|| too_many("symbols"))?;
+                code.extend(symbol.to_le_bytes());
+            }
+            VmInstr::Function {
+                name,
+                nlocals: count,
+            }
+            | VmInstr::Call { name, nargs: count } => {
+                code.push(if let VmInstr::Function { .. } = command.instr {
+                    OP_FUNCTION
+                } else {
+                    OP_CALL
+                });
+                let symbol = symbols.intern(name).ok_or_else(|| too_many("symbols"))?;
+                code.extend(symbol.to_le_bytes());
+                code.extend(count.to_le_bytes());
+            }
+            VmInstr::Return => code.push(OP_RETURN),
+        }
+    }
+
+    let mut buf: Vec<u8> = MAGIC.to_vec();
+    buf.push(VERSION);
+    buf.extend((symbols.values.len() as u16).to_le_bytes());
+    for symbol in &symbols.values {
+        let len = u16::try_from(symbol.len()).map_err(|_| WriteBytecodeError {
+            message: format!("symbol {} is longer than {} bytes", symbol, u16::MAX),
+        })?;
+        buf.extend(len.to_le_bytes());
+        buf.extend(symbol.as_bytes());
+    }
+    buf.extend((constants.values.len() as u16).to_le_bytes());
+    for constant in &constants.values {
+        buf.extend(constant.to_le_bytes());
+    }
+    let count = u32::try_from(commands.len()).map_err(|_| WriteBytecodeError {
+        message: format!("more than {} instructions in one file", u32::MAX),
+    })?;
+    buf.extend(count.to_le_bytes());
+    buf.extend(code);
+    Ok(buf)
+}
+
+/// Reads the bytecode front to back with bounds checks
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+impl<'a> Reader<'a> {
+    fn error(&self, message: String) -> BytecodeError {
+        BytecodeError {
+            offset: self.pos,
+            message,
+        }
+    }
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BytecodeError> {
+        if self.bytes.len() - self.pos < len {
+            return Err(self.error("unexpected end of input".to_string()));
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+    fn u8(&mut self) -> Result<u8, BytecodeError> {
+        Ok(self.take(1)?[0])
+    }
+    fn u16(&mut self) -> Result<u16, BytecodeError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+    fn u32(&mut self) -> Result<u32, BytecodeError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+    fn symbol(&mut self, symbols: &[String]) -> Result<String, BytecodeError> {
+        let idx = self.u16()?;
+        symbols
+            .get(idx as usize)
+            .cloned()
+            .ok_or_else(|| self.error(format!("symbol {} out of range", idx)))
+    }
+}
+
+/// Decode a .vmb file back into commands
+pub fn read_bytecode(bytes: &[u8]) -> Result<Vec<VMCommand>, BytecodeError> {
+    let mut reader = Reader { bytes, pos: 0 };
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(BytecodeError {
+            offset: 0,
+            message: "missing HVMB header".to_string(),
+        });
+    }
+    let version = reader.u8()?;
+    if version != VERSION {
+        return Err(reader.error(format!("unsupported version {}", version)));
+    }
+
+    let mut symbols = vec![];
+    for _ in 0..reader.u16()? {
+        let len = reader.u16()? as usize;
+        let symbol = std::str::from_utf8(reader.take(len)?)
+            .map_err(|_| reader.error("symbol is not valid UTF-8".to_string()))?;
+        symbols.push(symbol.to_string());
+    }
+    let mut constants = vec![];
+    for _ in 0..reader.u16()? {
+        constants.push(reader.u16()?);
+    }
+
+    let instr_count = reader.u32()?;
+    let mut commands = vec![];
+    for instr_nr in 1..=instr_count as usize {
+        let opcode = reader.u8()?;
+        let instr = match opcode {
+            OP_PUSH | OP_POP => {
+                let segment_code = reader.u8()?;
+                let segment = *SEGMENTS
+                    .get(segment_code as usize)
+                    .ok_or_else(|| reader.error(format!("unknown segment {}", segment_code)))?;
+                let mut index = reader.u16()?;
+                if opcode == OP_PUSH && segment == Segment::Constant {
+                    index = *constants
+                        .get(index as usize)
+                        .ok_or_else(|| reader.error(format!("constant {} out of range", index)))?;
+                }
+                if opcode == OP_PUSH {
+                    VmInstr::Push(segment, index)
+                } else {
+                    VmInstr::Pop(segment, index)
+                }
+            }
+            OP_LABEL => VmInstr::Label(reader.symbol(&symbols)?),
+            OP_GOTO => VmInstr::Goto(reader.symbol(&symbols)?),
+            OP_IF_GOTO => VmInstr::IfGoto(reader.symbol(&symbols)?),
+            OP_FUNCTION => VmInstr::Function {
+                name: reader.symbol(&symbols)?,
+                nlocals: reader.u16()?,
+            },
+            OP_CALL => VmInstr::Call {
+                name: reader.symbol(&symbols)?,
+                nargs: reader.u16()?,
+            },
+            OP_RETURN => VmInstr::Return,
+            op if (op as usize) < ArithOp::ALL.len() => VmInstr::Arith(ArithOp::ALL[op as usize]),
+            op => return Err(reader.error(format!("unknown opcode {:#04x}", op))),
+        };
+        let instr = instr.validated().map_err(|err| reader.error(err.message))?;
+
+        commands.push(VMCommand {
+            original: instr.to_string(),
+            line: instr_nr,
+            instr,
+        });
+    }
+
+    if reader.pos != bytes.len() {
+        return Err(reader.error("trailing bytes after last instruction".to_string()));
+    }
+
+    Ok(commands)
+}
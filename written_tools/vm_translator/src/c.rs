@@ -0,0 +1,206 @@
+//! C backend: one C file modelling the Hack RAM as `int16_t RAM[32768]`.
+//!
+//! The whole program becomes one computed-goto block (a GCC and Clang extension),
+//! VM functions and labels are C labels and return addresses are indices into a
+//! table of label addresses. Build and run headless with:
+//!
+//! ```text
+//! cc -O2 -o Prog Prog.c && ./Prog screen.pbm; echo $?
+//! ```
+//!
+//! The program stops once `Sys.init` returns or halts, the exit status is the low
+//! byte of the value on top of the stack. If a file name is given the SCREEN memory
+//! is written to it as a 512x256 PBM image.
+
+use crate::{
+    lowering::{lower, Location, LoweredInstr, LoweredProgram, ARG, LCL, SP, THAT, THIS},
+    ArithOp, Options, TranslateError, VmFile,
+};
+
+pub(crate) fn translate(files: &[VmFile], options: Options) -> Result<String, TranslateError> {
+    let program = lower(files, options.bootstrap)?;
+    Ok(emit(&program))
+}
+
+const PRELUDE: &str = r#"/* Generated by the Jack VM Translator */
+#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+/* VM labels which are never jumped to are fine */
+#pragma GCC diagnostic ignored "-Wunused-label"
+
+int16_t RAM[32768];
+
+/* every address wraps into the 32K RAM, arithmetic wraps at 16 bit */
+#define M(address) RAM[(uint16_t)(address) & 0x7FFF]
+#define WRAP(value) ((int16_t)(uint16_t)(value))
+#define PUSH(value) (M(RAM[0]) = WRAP(value), RAM[0] = WRAP(RAM[0] + 1))
+#define POP() (RAM[0] = WRAP(RAM[0] - 1), M(RAM[0]))
+#define TOP M(RAM[0] - 1)
+
+#define SCREEN 16384
+#define KBD 24576
+"#;
+
+const MAIN: &str = r#"
+/* SCREEN as PBM: 256 rows of 32 words, bit 0 of a word is the leftmost pixel */
+static int dump_screen(const char *path) {
+    FILE *file = fopen(path, "wb");
+    if (!file) {
+        perror(path);
+        return -1;
+    }
+    fprintf(file, "P4\n512 256\n");
+    for (int word = 0; word < 8192; word++) {
+        uint16_t value = (uint16_t)RAM[SCREEN + word];
+        for (int half = 0; half < 2; half++) {
+            unsigned char byte = 0;
+            for (int bit = 0; bit < 8; bit++) {
+                if (value & (1u << (half * 8 + bit))) {
+                    byte |= (unsigned char)(0x80 >> bit);
+                }
+            }
+            fputc(byte, file);
+        }
+    }
+    return fclose(file);
+}
+
+int main(int argc, char **argv) {
+    int status = run();
+    if (argc > 1 && dump_screen(argv[1]) != 0) {
+        return 255;
+    }
+    return status;
+}
+"#;
+
+fn ram(address: u16) -> String {
+    format!("RAM[{}]", address)
+}
+
+fn location(location: &Location) -> String {
+    match location {
+        Location::Constant(value) => value.to_string(),
+        Location::Indirect { base, offset } => format!("M({} + {})", ram(*base), offset),
+        Location::Direct(address) => ram(*address),
+    }
+}
+
+fn emit(program: &LoweredProgram) -> String {
+    let mut buf: Vec<String> = vec![PRELUDE.to_string(), "static int run(void) {".to_string()];
+
+    buf.push("    static void *const return_sites[] = {".to_string());
+    buf.push("        &&vm_exit,".to_string());
+    for return_site in 1..=program.return_sites {
+        buf.push(format!("        &&r{},", return_site));
+    }
+    buf.push("    };".to_string());
+    buf.push("    int16_t frame, return_site, value;".to_string());
+    buf.push(format!("    {} = 256;", ram(SP)));
+
+    if let Some(sys_init) = program.bootstrap {
+        buf.push("    /* bootstrap: call Sys.init 0 */".to_string());
+        for register in [LCL, ARG, THIS, THAT] {
+            buf.push(format!("    {} = -1;", ram(register)));
+        }
+        emit_call(&mut buf, sys_init, 0, 0);
+    }
+
+    for command in &program.commands {
+        buf.push(format!("    /* {} */", command.origin.replace("*/", "* /")));
+        match &command.instr {
+            LoweredInstr::Arith(op) => buf.push(format!("    {}", arith(*op))),
+            LoweredInstr::Push(source) => buf.push(format!("    PUSH({});", location(source))),
+            LoweredInstr::Pop(Location::Constant(_)) => {
+                unreachable!("pop constant is not possible")
+            }
+            LoweredInstr::Pop(target) => {
+                // the address has to be computed before SP changes
+                buf.push("    value = POP();".to_string());
+                buf.push(format!("    {} = value;", location(target)));
+            }
+            LoweredInstr::Label(id) => {
+                buf.push(format!("l{}: /* {} */", id, program.labels[*id]));
+            }
+            LoweredInstr::Goto(id) => buf.push(format!("    goto l{};", id)),
+            LoweredInstr::IfGoto(id) => buf.push(format!("    if (POP() != 0) goto l{};", id)),
+            LoweredInstr::Halt => buf.push("    goto vm_exit;".to_string()),
+            LoweredInstr::Function { id, nlocals } => {
+                buf.push(format!("f{}: /* {} */", id, program.functions[*id]));
+                for _ in 0..*nlocals {
+                    buf.push("    PUSH(0);".to_string());
+                }
+            }
+            LoweredInstr::Call {
+                id,
+                nargs,
+                return_site,
+            } => emit_call(&mut buf, *id, *nargs, *return_site),
+            LoweredInstr::Return => emit_return(&mut buf, program.return_sites),
+        }
+    }
+
+    buf.push("    /* end of program */".to_string());
+    buf.push("vm_exit:".to_string());
+    buf.push("    return (uint16_t)TOP & 0xFF;".to_string());
+    buf.push("}".to_string());
+    buf.push(MAIN.to_string());
+
+    buf.join("\n")
+}
+
+fn arith(op: ArithOp) -> String {
+    match op {
+        ArithOp::Neg => "TOP = WRAP(-TOP);".to_string(),
+        ArithOp::Not => "TOP = WRAP(~TOP);".to_string(),
+        _ => {
+            let expr = match op {
+                ArithOp::Add => "WRAP(TOP + value)",
+                ArithOp::Sub => "WRAP(TOP - value)",
+                ArithOp::And => "TOP & value",
+                ArithOp::Or => "TOP | value",
+                ArithOp::Eq => "TOP == value ? -1 : 0",
+                ArithOp::Gt => "TOP > value ? -1 : 0",
+                ArithOp::Lt => "TOP < value ? -1 : 0",
+                ArithOp::Neg | ArithOp::Not => unreachable!(),
+            };
+            format!("value = POP(); TOP = {};", expr)
+        }
+    }
+}
+
+fn emit_call(buf: &mut Vec<String>, id: usize, nargs: u16, return_site: usize) {
+    buf.push(format!("    PUSH({});", return_site));
+    for register in [LCL, ARG, THIS, THAT] {
+        buf.push(format!("    PUSH({});", ram(register)));
+    }
+    buf.push(format!(
+        "    {} = WRAP({} - {});",
+        ram(ARG),
+        ram(SP),
+        5 + nargs as u32
+    ));
+    buf.push(format!("    {} = {};", ram(LCL), ram(SP)));
+    buf.push(format!("    goto f{};", id));
+    if return_site != 0 {
+        buf.push(format!("r{}:", return_site));
+    }
+}
+
+fn emit_return(buf: &mut Vec<String>, return_sites: usize) {
+    buf.push(format!("    frame = {};", ram(LCL)));
+    buf.push("    return_site = M(frame - 5);".to_string());
+    buf.push("    value = POP();".to_string());
+    buf.push(format!("    M({}) = value;", ram(ARG)));
+    buf.push(format!("    {} = WRAP({} + 1);", ram(SP), ram(ARG)));
+    for (distance, register) in [(1, THAT), (2, THIS), (3, ARG), (4, LCL)] {
+        buf.push(format!("    {} = M(frame - {});", ram(register), distance));
+    }
+    buf.push(format!(
+        "    if ((uint16_t)return_site > {}) abort();",
+        return_sites
+    ));
+    buf.push("    goto *return_sites[(uint16_t)return_site];".to_string());
+}
@@ -0,0 +1,314 @@
+use std::{fmt, str::FromStr};
+
+/// Memory segments addressable by push and pop
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Segment {
+    Local,
+    Argument,
+    This,
+    That,
+    Static,
+    Temp,
+    Constant,
+    Pointer,
+}
+impl FromStr for Segment {
+    type Err = ParseVmError;
+
+    fn from_str(segment: &str) -> Result<Self, Self::Err> {
+        Ok(match segment {
+            "local" => Segment::Local,
+            "argument" => Segment::Argument,
+            "this" => Segment::This,
+            "that" => Segment::That,
+            "static" => Segment::Static,
+            "temp" => Segment::Temp,
+            "constant" => Segment::Constant,
+            "pointer" => Segment::Pointer,
+            _ => return Err(ParseVmError::new(format!("unknown segment {:?}", segment))),
+        })
+    }
+}
+impl fmt::Display for Segment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Segment::Local => "local",
+            Segment::Argument => "argument",
+            Segment::This => "this",
+            Segment::That => "that",
+            Segment::Static => "static",
+            Segment::Temp => "temp",
+            Segment::Constant => "constant",
+            Segment::Pointer => "pointer",
+        })
+    }
+}
+
+/// Arithmetic and logical stack commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Neg,
+    Eq,
+    Gt,
+    Lt,
+    And,
+    Or,
+    Not,
+}
+impl ArithOp {
+    pub const ALL: [ArithOp; 9] = [
+        ArithOp::Add,
+        ArithOp::Sub,
+        ArithOp::Neg,
+        ArithOp::Eq,
+        ArithOp::Gt,
+        ArithOp::Lt,
+        ArithOp::And,
+        ArithOp::Or,
+        ArithOp::Not,
+    ];
+}
+impl FromStr for ArithOp {
+    type Err = ParseVmError;
+
+    fn from_str(op: &str) -> Result<Self, Self::Err> {
+        ArithOp::ALL
+            .into_iter()
+            .find(|candidate| candidate.to_string() == op)
+            .ok_or_else(|| ParseVmError::new(format!("unknown command {:?}", op)))
+    }
+}
+impl fmt::Display for ArithOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ArithOp::Add => "add",
+            ArithOp::Sub => "sub",
+            ArithOp::Neg => "neg",
+            ArithOp::Eq => "eq",
+            ArithOp::Gt => "gt",
+            ArithOp::Lt => "lt",
+            ArithOp::And => "and",
+            ArithOp::Or => "or",
+            ArithOp::Not => "not",
+        })
+    }
+}
+
+/// One VM instruction. Parsing rejects states the VM can not execute,
+/// like `pop constant`, `pointer 2` or `temp 8`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VmInstr {
+    Arith(ArithOp),
+    Push(Segment, u16),
+    Pop(Segment, u16),
+    Label(String),
+    Goto(String),
+    IfGoto(String),
+    Function { name: String, nlocals: u16 },
+    Call { name: String, nargs: u16 },
+    Return,
+}
+impl VmInstr {
+    /// Reject instructions the VM can not execute
+    pub(crate) fn validated(self) -> Result<Self, ParseVmError> {
+        match self {
+            VmInstr::Pop(Segment::Constant, _) => {
+                Err(ParseVmError::new("can not pop into constant".to_string()))
+            }
+            VmInstr::Push(Segment::Pointer, index) | VmInstr::Pop(Segment::Pointer, index)
+                if index > 1 =>
+            {
+                Err(ParseVmError::new(format!(
+                    "pointer {} out of range 0..1",
+                    index
+                )))
+            }
+            VmInstr::Push(Segment::Temp, index) | VmInstr::Pop(Segment::Temp, index)
+                if index > 7 =>
+            {
+                Err(ParseVmError::new(format!(
+                    "temp {} out of range 0..7",
+                    index
+                )))
+            }
+            // only reachable from bytecode, the text parser already stops at 32767
+            VmInstr::Push(segment, index) | VmInstr::Pop(segment, index) if index > 32767 => Err(
+                ParseVmError::new(format!("{} {} out of range 0..32767", segment, index)),
+            ),
+            VmInstr::Function { nlocals, .. } if nlocals > 32767 => Err(ParseVmError::new(
+                format!("nlocals {} out of range 0..32767", nlocals),
+            )),
+            VmInstr::Call { nargs, .. } if nargs > 32767 => Err(ParseVmError::new(format!(
+                "nargs {} out of range 0..32767",
+                nargs
+            ))),
+            // a bytecode symbol can hold what no word of a .vm line can
+            VmInstr::Function { name, .. } | VmInstr::Call { name, .. }
+                if name.is_empty() || name.contains(char::is_whitespace) =>
+            {
+                Err(ParseVmError::new(format!(
+                    "function name {:?} is empty or contains whitespace",
+                    name
+                )))
+            }
+            VmInstr::Label(label) | VmInstr::Goto(label) | VmInstr::IfGoto(label)
+                if !is_label_name(&label) =>
+            {
+                Err(ParseVmError::new(format!(
+                    "label {:?} may only use letters, digits, _, . and : and not start with a digit",
+                    label
+                )))
+            }
+            instr => Ok(instr),
+        }
+    }
+}
+
+/// Label names of the VM language, generated assembly labels rely on them having no `$`
+fn is_label_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':'))
+}
+impl FromStr for VmInstr {
+    type Err = ParseVmError;
+
+    fn from_str(string_instr: &str) -> Result<Self, Self::Err> {
+        let split_input_string: Vec<&str> = string_instr.split_whitespace().collect();
+
+        let arg_count = match split_input_string.first() {
+            None => return Err(ParseVmError::new("empty instruction".to_string())),
+            Some(&"push" | &"pop" | &"function" | &"call") => 2,
+            Some(&"label" | &"goto" | &"if-goto") => 1,
+            Some(_) => 0,
+        };
+        if split_input_string.len() != arg_count + 1 {
+            return Err(ParseVmError::new(format!(
+                "{:?} expects {} argument(s), found {}",
+                split_input_string[0],
+                arg_count,
+                split_input_string.len() - 1
+            )));
+        }
+        let number = |idx: usize| -> Result<u16, ParseVmError> {
+            split_input_string[idx]
+                .parse::<u16>()
+                .ok()
+                .filter(|number| *number <= 32767)
+                .ok_or_else(|| {
+                    ParseVmError::new(format!(
+                        "expected a number between 0 and 32767, found {:?}",
+                        split_input_string[idx]
+                    ))
+                })
+        };
+
+        let instr = match split_input_string[0] {
+            "push" => VmInstr::Push(split_input_string[1].parse()?, number(2)?),
+            "pop" => VmInstr::Pop(split_input_string[1].parse()?, number(2)?),
+            "label" => VmInstr::Label(split_input_string[1].to_owned()),
+            "goto" => VmInstr::Goto(split_input_string[1].to_owned()),
+            "if-goto" => VmInstr::IfGoto(split_input_string[1].to_owned()),
+            "function" => VmInstr::Function {
+                name: split_input_string[1].to_owned(),
+                nlocals: number(2)?,
+            },
+            "call" => VmInstr::Call {
+                name: split_input_string[1].to_owned(),
+                nargs: number(2)?,
+            },
+            "return" => VmInstr::Return,
+            op => VmInstr::Arith(op.parse()?),
+        };
+
+        instr.validated()
+    }
+}
+impl fmt::Display for VmInstr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmInstr::Arith(op) => write!(f, "{}", op),
+            VmInstr::Push(segment, index) => write!(f, "push {} {}", segment, index),
+            VmInstr::Pop(segment, index) => write!(f, "pop {} {}", segment, index),
+            VmInstr::Label(label) => write!(f, "label {}", label),
+            VmInstr::Goto(label) => write!(f, "goto {}", label),
+            VmInstr::IfGoto(label) => write!(f, "if-goto {}", label),
+            VmInstr::Function { name, nlocals } => write!(f, "function {} {}", name, nlocals),
+            VmInstr::Call { name, nargs } => write!(f, "call {} {}", name, nargs),
+            VmInstr::Return => write!(f, "return"),
+        }
+    }
+}
+
+/// A VM instruction together with where it came from
+#[derive(Debug, Clone)]
+pub struct VMCommand {
+    /// The instruction as written in the .vm file, without comments
+    pub original: String,
+    /// 1-based line in the .vm file
+    pub line: usize,
+    pub instr: VmInstr,
+}
+impl VMCommand {
+    pub fn from_string(string_instr: &str) -> Result<Self, ParseVmError> {
+        Ok(VMCommand {
+            original: string_instr.to_owned(),
+            line: 0,
+            instr: string_instr.parse()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseVmError {
+    /// 1-based line in the .vm file, 0 if unknown
+    pub line: usize,
+    pub message: String,
+}
+impl ParseVmError {
+    pub(crate) fn new(message: String) -> Self {
+        ParseVmError { line: 0, message }
+    }
+}
+impl fmt::Display for ParseVmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+impl std::error::Error for ParseVmError {}
+
+/// Parse the contents of a .vm file, skipping comments and empty lines
+pub fn parse_vm(source: &str) -> Result<Vec<VMCommand>, ParseVmError> {
+    source
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| {
+            if line.contains("//") {
+                (idx + 1, line.split_at(line.find("//").unwrap()).0)
+            } else {
+                (idx + 1, line)
+            }
+        })
+        .map(|(line_nr, line)| (line_nr, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(line_nr, line)| {
+            VMCommand::from_string(line)
+                .map(|command| VMCommand {
+                    line: line_nr,
+                    ..command
+                })
+                .map_err(|err| ParseVmError {
+                    line: line_nr,
+                    ..err
+                })
+        })
+        .collect()
+}
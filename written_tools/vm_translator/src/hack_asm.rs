@@ -0,0 +1,791 @@
+use std::collections::HashMap;
+
+use crate::{ArithOp, Segment, SourceMapEntry, TranslateError, VMCommand, VmFile, VmInstr};
+
+pub(crate) fn make_bootstrap_code() -> String {
+    // * Bootstrap code (should be written in assembly)
+    // SP = 256
+    // **call** Sys.init
+    let out_buf: Vec<&str> = vec![
+        // Start of by setting LCL ARG THIS THAT to -1
+        "D=-1",
+        "@LCL",
+        "M=D",
+        "@ARG",
+        "M=D",
+        "@THIS",
+        "M=D",
+        "@THAT",
+        "M=D",
+        // Set SP to 256
+        "@256",
+        "D=A",
+        "@SP",
+        "M=D",
+        "",
+        "@bootstrap",
+        "D=A",
+        "@SP",
+        "A=M",
+        "M=D", // *SP = D
+        "",
+        "@SP", // Inc SP
+        "M=M+1",
+        "\n",
+        "@LCL",
+        "D=M",
+        "@SP",
+        "A=M",
+        "M=D", // *SP = D
+        "",
+        "@SP", // Inc SP
+        "M=M+1",
+        "\n",
+        "@ARG",
+        "D=M",
+        "@SP",
+        "A=M",
+        "M=D", // *SP = D
+        "",
+        "@SP", // Inc SP
+        "M=M+1",
+        "\n",
+        "@THIS",
+        "D=M",
+        "@SP",
+        "A=M",
+        "M=D", // *SP = D
+        "",
+        "@SP", // Inc SP
+        "M=M+1",
+        "\n",
+        "@THAT",
+        "D=M",
+        "@SP",
+        "A=M",
+        "M=D", // *SP = D
+        "",
+        "@SP", // Inc SP
+        "M=M+1",
+        "\n",
+        // ARG = SP -5
+        "@SP",
+        "D=M",
+        "@5",
+        "D=D-A",
+        "@ARG",
+        "M=D",
+        // Set LCL = SP
+        "@SP",
+        "D=M",
+        "@LCL",
+        "M=D",
+        // Finnaly jump to Sys.init
+        "@Sys.init",
+        "0; JMP",
+        "(bootstrap)",
+        "// End of Bootstrap\n\n\n",
+    ];
+
+    out_buf.join("\n")
+}
+
+/// Translate one file, returning its assembly and the source map entries
+/// with assembly lines counted from the start of the file's code
+pub(crate) fn file_to_asm_code(
+    file: &VmFile,
+    labels: &mut LabelGenerator,
+) -> Result<(String, Vec<SourceMapEntry>), TranslateError> {
+    let core_name = file.name.as_str();
+
+    let mut assembler_code: Vec<String> = vec![];
+    let mut source_map_entries = vec![];
+    let mut asm_line = 1;
+    let mut current_function = CurrentVMFunction {
+        active_flag: false,
+        name: "".to_string(),
+    };
+    for instr in file.commands()? {
+        let instr_asm = instr.to_asm(core_name, labels, &mut current_function)?;
+
+        // every block ends with a newline and blocks get joined by another one
+        let asm_line_count = instr_asm.matches('\n').count();
+        source_map_entries.push(SourceMapEntry {
+            asm_start: asm_line,
+            asm_end: asm_line + asm_line_count - 1,
+            vm_file: format!("{}.vm", core_name),
+            vm_line: instr.line,
+            function: if current_function.active_flag {
+                current_function.name.clone()
+            } else {
+                "-".to_string()
+            },
+        });
+        asm_line += asm_line_count + 1;
+
+        assembler_code.push(instr_asm);
+    }
+
+    let assembler_code: String = assembler_code.join("\n");
+
+    Ok((assembler_code, source_map_entries))
+}
+
+#[derive(Debug, Clone)]
+struct CurrentVMFunction {
+    active_flag: bool,
+    name: String,
+}
+impl CurrentVMFunction {
+    /// Prefix for labels and return addresses: the function name,
+    /// or the file name for code before the first `function` line
+    fn scope<'a>(&'a self, file_core_name: &'a str) -> &'a str {
+        if self.active_flag {
+            &self.name
+        } else {
+            file_core_name
+        }
+    }
+}
+
+/// Hands out assembly labels for a whole translation run (all files of a folder),
+/// so comparison and return labels never repeat and duplicate definitions are caught.
+/// A VM label becomes `scope$label` and may not contain `$` or start with a digit, the
+/// generated labels all end in `$` and a number so no VM label can ever take their name
+#[derive(Debug, Default)]
+pub(crate) struct LabelGenerator {
+    comp_label_counter: usize,
+    return_label_counts: HashMap<String, usize>,
+    // label -> (file, line) of its definition
+    defined: HashMap<String, (String, usize)>,
+}
+impl LabelGenerator {
+    pub(crate) fn define(
+        &mut self,
+        label: &str,
+        file: &str,
+        line: usize,
+    ) -> Result<(), TranslateError> {
+        if let Some((first_file, first_line)) = self.defined.get(label) {
+            return Err(TranslateError::LabelCollision {
+                label: label.to_string(),
+                first_file: first_file.clone(),
+                first_line: *first_line,
+                file: file.to_string(),
+                line,
+            });
+        }
+        self.defined
+            .insert(label.to_string(), (file.to_string(), line));
+        Ok(())
+    }
+    fn next_comp_label(&mut self) -> usize {
+        let label = self.comp_label_counter;
+        self.comp_label_counter += 1;
+        label
+    }
+    fn next_return_label(&mut self, scope: &str) -> String {
+        let count = self
+            .return_label_counts
+            .entry(scope.to_string())
+            .or_insert(0);
+        let label = format!("{}$ret${}", scope, count);
+        *count += 1;
+        label
+    }
+}
+
+impl VMCommand {
+    fn to_asm(
+        &self,
+        file_core_name: &str,
+        labels: &mut LabelGenerator,
+        current_function_def: &mut CurrentVMFunction,
+    ) -> Result<String, TranslateError> {
+        let file_name = format!("{}.vm", file_core_name);
+        let line = self.line;
+        let mut buffer_string = format!("// {}\n", self.original);
+        match &self.instr {
+            VmInstr::Arith(op) => {
+                match op {
+                    ArithOp::Add => {
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "D=M\n"; // D = *SP
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "A=M\n"; // A = *SP
+
+                        buffer_string += "D=D+A\n"; // Now perform OP
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=D\n"; // Push Onto The Stack
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n"; // Fix Stack Location Back
+                    }
+                    ArithOp::Sub => {
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "D=M\n"; // D = *SP
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "A=M\n"; // A = *SP
+
+                        buffer_string += "D=A-D\n"; // Now perform OP
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=D\n"; // Push Onto The Stack
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n"; // Fix Stack Location Back
+                    }
+                    ArithOp::Neg => {
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "D=M\n"; // D = *SP
+
+                        buffer_string += "D=-D\n"; // Now perform OP
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=D\n"; // Push Onto The Stack
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n"; // Fix Stack Location Back
+                    }
+                    ArithOp::Eq => {
+                        let comp_label_counter = labels.next_comp_label();
+                        let label_true = &format!("(_COMP_LABEL_TRUE${})\n", comp_label_counter);
+                        let label_false = &format!("(_COMP_LABEL_FALSE${})\n", comp_label_counter);
+                        let label_end = &format!("(_COMP_LABEL_END${})\n", comp_label_counter);
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "D=M\n"; // D = *SP
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "A=M\n"; // A = *SP
+
+                        buffer_string += "D=A-D\n"; // Now perform OP
+
+                        // jump if true
+                        buffer_string += &format!("@_COMP_LABEL_TRUE${}\n", comp_label_counter);
+                        buffer_string += "D; JEQ\n";
+                        buffer_string += &format!("@_COMP_LABEL_FALSE${}\n", comp_label_counter);
+                        buffer_string += "0; JMP\n";
+
+                        buffer_string += label_true;
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=-1\n"; // Push Onto The Stack
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n"; // Fix Stack Location Back
+
+                        buffer_string += &format!("@_COMP_LABEL_END${}\n", comp_label_counter);
+                        buffer_string += "0; JMP\n";
+
+                        buffer_string += label_false;
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=0\n"; // Push Onto The Stack
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n"; // Fix Stack Location Back
+
+                        buffer_string += label_end;
+                    }
+                    ArithOp::Gt | ArithOp::Lt => {
+                        let comp_label_counter = labels.next_comp_label();
+                        let label =
+                            |name: &str| format!("_COMP_LABEL_{}${}", name, comp_label_counter);
+                        let (jump, x_neg_y_nonneg, x_nonneg_y_neg) = match op {
+                            ArithOp::Gt => ("JGT", label("FALSE"), label("TRUE")),
+                            _ => ("JLT", label("TRUE"), label("FALSE")),
+                        };
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "D=M\n"; // D = *SP
+                        buffer_string += "@R13\nM=D\n"; // y in R13
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "D=M\n"; // D = *SP
+                        buffer_string += "@R14\nM=D\n"; // x in R14
+
+                        // x - y can overflow if the signs differ, but then the sign decides
+                        buffer_string += &format!("@{}\n", label("X_NEG"));
+                        buffer_string += "D; JLT\n";
+                        buffer_string += "@R13\nD=M\n";
+                        buffer_string += &format!("@{}\n", x_nonneg_y_neg);
+                        buffer_string += "D; JLT\n";
+                        buffer_string += &format!("@{}\n", label("SUB"));
+                        buffer_string += "0; JMP\n";
+                        buffer_string += &format!("({})\n", label("X_NEG"));
+                        buffer_string += "@R13\nD=M\n";
+                        buffer_string += &format!("@{}\n", x_neg_y_nonneg);
+                        buffer_string += "D; JGE\n";
+
+                        // same signs, x - y does not overflow
+                        buffer_string += &format!("({})\n", label("SUB"));
+                        buffer_string += "@R13\nD=M\n";
+                        buffer_string += "@R14\nD=M-D\n"; // Now perform OP
+
+                        // jump if true
+                        buffer_string += &format!("@{}\n", label("TRUE"));
+                        buffer_string += &format!("D; {}\n", jump);
+                        buffer_string += &format!("@{}\n", label("FALSE"));
+                        buffer_string += "0; JMP\n";
+
+                        buffer_string += &format!("({})\n", label("TRUE"));
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=-1\n"; // Push Onto The Stack
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n"; // Fix Stack Location Back
+
+                        buffer_string += &format!("@{}\n", label("END"));
+                        buffer_string += "0; JMP\n";
+
+                        buffer_string += &format!("({})\n", label("FALSE"));
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=0\n"; // Push Onto The Stack
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n"; // Fix Stack Location Back
+
+                        buffer_string += &format!("({})\n", label("END"));
+                    }
+                    ArithOp::And => {
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "D=M\n"; // D = *SP
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "A=M\n"; // A = *SP
+
+                        buffer_string += "D=D&A\n"; // Now perform OP
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=D\n"; // Push Onto The Stack
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n"; // Fix Stack Location Back
+                    }
+                    ArithOp::Or => {
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "D=M\n"; // D = *SP
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "A=M\n"; // A = *SP
+
+                        buffer_string += "D=D|A\n"; // Now perform OP
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=D\n"; // Push Onto The Stack
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n"; // Fix Stack Location Back
+                    }
+                    ArithOp::Not => {
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "D=M\n"; // D = *SP
+
+                        buffer_string += "D=!D\n"; // Now perform OP
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=D\n"; // Push Onto The Stack
+
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n"; // Fix Stack Location Back
+                    }
+                }
+            }
+            VmInstr::Push(segment, index) => {
+                match segment {
+                    Segment::Local | Segment::Argument | Segment::This | Segment::That => {
+                        // *this + arg2 => R13
+                        // [R13] => [*this + arg2]
+                        // *SP = [*this + arg2]
+                        // *SP++
+
+                        // Calculate Offset from specified Memory Region
+                        buffer_string += match segment {
+                            Segment::Local => "@LCL\n",
+                            Segment::Argument => "@ARG\n",
+                            Segment::This => "@THIS\n",
+                            Segment::That => "@THAT\n",
+                            _ => unreachable!(),
+                        };
+                        // add offset
+                        buffer_string += "D=M\n";
+                        buffer_string += &format!("@{}\n", index);
+                        buffer_string += "A=D+A\n";
+                        buffer_string += "D=M\n";
+
+                        // Push To Stack
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=D\n"; // D = *SP
+                                                  // Inc SP
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n";
+                    }
+                    Segment::Static => {
+                        buffer_string += &format!("@{}.{}\n", file_core_name, index);
+                        buffer_string += "D=M\n"; // Get Data At static
+
+                        // Push To Stack
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=D\n"; // D = *SP
+                                                  // Inc SP
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n";
+                    }
+                    Segment::Temp => {
+                        buffer_string += "@5\n";
+                        // add offset
+                        buffer_string += "D=A\n";
+                        buffer_string += &format!("@{}\n", index);
+                        buffer_string += "A=D+A\n";
+                        buffer_string += "D=M\n";
+
+                        // Push To Stack
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=D\n"; // D = *SP
+                                                  // Inc SP
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n";
+                    }
+                    Segment::Constant => {
+                        // D = const
+                        // *SP = D
+                        // *SP = *SP + 1
+                        buffer_string += &format!("@{}\n", index);
+                        buffer_string += "D=A\n";
+                        // Push To Stack
+                        buffer_string += "@SP\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "M=D\n"; // D = *SP
+                                                  // Inc SP
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M+1\n";
+                    }
+                    Segment::Pointer => {
+                        match index {
+                            0 => {
+                                buffer_string += "@THIS\n";
+                                // add offset
+                                buffer_string += "D=M\n";
+                                // Push To Stack
+                                buffer_string += "@SP\n";
+                                buffer_string += "A=M\n";
+                                buffer_string += "M=D\n"; // D = *SP
+                                                          // Inc SP
+                                buffer_string += "@SP\n";
+                                buffer_string += "M=M+1\n";
+                            }
+                            1 => {
+                                buffer_string += "@THAT\n";
+                                // add offset
+                                buffer_string += "D=M\n";
+                                // Push To Stack
+                                buffer_string += "@SP\n";
+                                buffer_string += "A=M\n";
+                                buffer_string += "M=D\n"; // D = *SP
+                                                          // Inc SP
+                                buffer_string += "@SP\n";
+                                buffer_string += "M=M+1\n";
+                            }
+                            _ => unreachable!("INVALID pointer number"),
+                        }
+                    }
+                }
+            }
+            VmInstr::Pop(segment, index) => {
+                match segment {
+                    Segment::Local | Segment::Argument | Segment::This | Segment::That => {
+                        // Calculate Offset from specified Memory Region
+                        buffer_string += match segment {
+                            Segment::Local => "@LCL\n",
+                            Segment::Argument => "@ARG\n",
+                            Segment::This => "@THIS\n",
+                            Segment::That => "@THAT\n",
+                            _ => unreachable!(),
+                        };
+                        // add offset
+                        buffer_string += "D=M\n";
+                        buffer_string += &format!("@{}\nD=D+A\n", index);
+                        buffer_string += "@R13\nM=D\n"; // save temp in R13
+                                                        // Get Data From Stack
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "D=M\n"; // D = *SP
+                                                  // Set Data At Location Specified
+                        buffer_string += "@R13\nA=M\nM=D\n";
+                    }
+                    Segment::Static => {
+                        // Get Data From Stack
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "D=M\n"; // D = *SP
+                                                  // Set Data At Location Specified
+                        buffer_string += &format!("@{}.{}\n", file_core_name, index);
+                        buffer_string += "M=D\n";
+                    }
+                    Segment::Temp => {
+                        buffer_string += "@5\n";
+                        // add offset
+                        buffer_string += "D=A\n";
+                        buffer_string += &format!("@{}\nD=D+A\n", index);
+                        buffer_string += "@R13\nM=D\n"; // save temp in R13
+                                                        // Get Data From Stack
+                        buffer_string += "@SP\n";
+                        buffer_string += "M=M-1\n";
+                        buffer_string += "A=M\n";
+                        buffer_string += "D=M\n"; // D = *SP
+                                                  // Set Data At Location Specified
+                        buffer_string += "@R13\nA=M\nM=D\n";
+                    }
+                    Segment::Pointer => {
+                        match index {
+                            0 => {
+                                // Get Data From Stack
+                                buffer_string += "@SP\n";
+                                buffer_string += "M=M-1\n";
+                                buffer_string += "A=M\n";
+                                buffer_string += "D=M\n"; // D = *SP
+                                                          // Set Data At Location Specified
+                                buffer_string += "@THIS\nM=D\n";
+                            }
+                            1 => {
+                                // Get Data From Stack
+                                buffer_string += "@SP\n";
+                                buffer_string += "M=M-1\n";
+                                buffer_string += "A=M\n";
+                                buffer_string += "D=M\n"; // D = *SP
+                                                          // Set Data At Location Specified
+                                buffer_string += "@THAT\nM=D\n";
+                            }
+                            _ => unreachable!("INVALID pointer number"),
+                        }
+                    }
+                    Segment::Constant => unreachable!("pop constant is not possible"),
+                }
+            }
+            VmInstr::Label(label_name) => {
+                let label = format!(
+                    "{}${}",
+                    current_function_def.scope(file_core_name),
+                    label_name
+                );
+                labels.define(&label, &file_name, line)?;
+                buffer_string += &format!("({})\n", label);
+            }
+            VmInstr::Goto(label_name) => {
+                buffer_string += &format!(
+                    "@{}${}\n",
+                    current_function_def.scope(file_core_name),
+                    label_name
+                );
+                buffer_string += "0; JMP\n";
+            }
+            VmInstr::IfGoto(label_name) => {
+                // Get Data From Stack
+                buffer_string += "@SP\n";
+                buffer_string += "M=M-1\n";
+                buffer_string += "A=M\n";
+                buffer_string += "D=M\n"; // D = *SP
+                buffer_string += &format!(
+                    "@{}${}\n",
+                    current_function_def.scope(file_core_name),
+                    label_name
+                );
+                buffer_string += "D; JNE\n";
+            }
+            VmInstr::Function {
+                name: function_name,
+                nlocals: local_count,
+            } => {
+                *current_function_def = CurrentVMFunction {
+                    active_flag: true,
+                    name: function_name.clone(),
+                };
+                labels.define(&current_function_def.name, &file_name, line)?;
+                buffer_string += &format!("({})\n", current_function_def.name.clone());
+                for _ in 0..*local_count {
+                    // load 0 to push as local variable
+                    buffer_string += "@0\n";
+                    buffer_string += "D=A\n";
+                    // Push To Stack
+                    buffer_string += "@SP\n";
+                    buffer_string += "A=M\n";
+                    buffer_string += "M=D\n"; // D = *SP
+                                              // Inc SP
+                    buffer_string += "@SP\n";
+                    buffer_string += "M=M+1\n";
+                }
+            }
+            VmInstr::Return => {
+                // endFrame in @R13
+                // retAddr in @R14
+
+                // first save Local so we can get the return value in a second
+                buffer_string += "@LCL\n";
+                buffer_string += "D=M\n";
+                buffer_string += "@R13\n";
+                buffer_string += "M=D\n";
+
+                // second get the return address to jump to at the end of the return statement
+                // its in LCL-5
+                // save in @R14
+                buffer_string += "@R13\n";
+                buffer_string += "D=M\n";
+                buffer_string += "@5\n";
+                buffer_string += "A=D-A\n";
+                buffer_string += "D=M\n";
+                buffer_string += "@R14\n";
+                buffer_string += "M=D\n";
+
+                // set the return value in Argument 0, top of the Stack for caller
+                // *ARG = pop()
+                // Get Data From Stack
+                buffer_string += "@SP\n";
+                buffer_string += "M=M-1\n";
+                buffer_string += "A=M\n";
+                buffer_string += "D=M\n"; // D = *SP
+                                          // Set Data At ARG
+                buffer_string += "@ARG\nA=M\nM=D\n";
+
+                // Reset SP to ARG+1
+                buffer_string += "@ARG\n";
+                buffer_string += "D=M\n";
+                buffer_string += "@SP\n";
+                buffer_string += "M=D+1\n";
+
+                // Restore Stack Frame of Caller
+                for to_save in ["@THAT\n", "@THIS\n", "@ARG\n", "@LCL\n"]
+                    .iter()
+                    .enumerate()
+                {
+                    // *(endframe - n) // Get Value for Restoration
+                    buffer_string += "@R13\n";
+                    buffer_string += "A=M\n";
+                    buffer_string += "D=A\n";
+                    buffer_string += &format!("@{}\n", to_save.0 + 1);
+                    buffer_string += "A=D-A\n";
+                    buffer_string += "D=M\n";
+
+                    // Restore
+                    buffer_string += to_save.1;
+                    // get value
+                    buffer_string += "M=D\n";
+                }
+
+                // Go back to retAddr
+                buffer_string += "@R14\n";
+                buffer_string += "A=M\n";
+                buffer_string += "0 ; JMP // Return from function\n";
+            }
+            VmInstr::Call {
+                name: function_name,
+                nargs: arg_count,
+            } => {
+                // Push Return address
+                let return_label =
+                    labels.next_return_label(current_function_def.scope(file_core_name));
+                labels.define(&return_label, &file_name, line)?;
+                buffer_string += &format!("@{}\n", return_label);
+                buffer_string += "D=A\n";
+                // Push To Stack
+                buffer_string += "@SP\n";
+                buffer_string += "A=M\n";
+                buffer_string += "M=D\n"; // D = *SP
+                                          // Inc SP
+                buffer_string += "@SP\n";
+                buffer_string += "M=M+1\n";
+
+                for to_save in ["@LCL\n", "@ARG\n", "@THIS\n", "@THAT\n"] {
+                    buffer_string += to_save;
+                    // get value
+                    buffer_string += "D=M\n";
+                    // Push To Stack
+                    buffer_string += "@SP\n";
+                    buffer_string += "A=M\n";
+                    buffer_string += "M=D\n"; // D = *SP
+                                              // Inc SP
+                    buffer_string += "@SP\n";
+                    buffer_string += "M=M+1\n";
+                }
+
+                // set new ARG Pointer to SP-5-nArgs
+                buffer_string += "@SP\n";
+                buffer_string += "D=M\n";
+                // -5
+                buffer_string += "@5\n";
+                buffer_string += "D=D-A\n";
+                // -nArgs
+                buffer_string += &format!("@{}\n", arg_count);
+                buffer_string += "D=D-A\n";
+                // set ARG
+                buffer_string += "@ARG\n";
+                buffer_string += "M=D\n";
+
+                // set LCL to SP
+                buffer_string += "@SP\n";
+                buffer_string += "D=M\n";
+                buffer_string += "@LCL\n";
+                buffer_string += "M=D\n";
+
+                // call futncion
+                buffer_string += &format!("@{}\n", function_name);
+                buffer_string += "0; JMP // Jump to Function\n";
+
+                // return label
+                buffer_string += &format!("({}) // return label\n", return_label);
+            }
+        }
+        if buffer_string.is_empty() {
+            eprintln!("EMPTY!, {:?}", self);
+        }
+        Ok(buffer_string)
+    }
+}
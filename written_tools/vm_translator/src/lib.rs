@@ -0,0 +1,180 @@
+//! Jack VM Translator: translates the stack based VM language of the nand2tetris
+//! course into Hack assembly.
+//!
+//! ```no_run
+//! use vm_translator::{translate, Options, VmFile, VmSource};
+//!
+//! let files = vec![VmFile {
+//!     name: "Sys".to_string(),
+//!     source: VmSource::Text("function Sys.init 0\nlabel LOOP\ngoto LOOP".to_string()),
+//! }];
+//! let options = Options {
+//!     bootstrap: true,
+//!     ..Options::default()
+//! };
+//! let asm = translate(&files, options).unwrap();
+//! ```
+
+mod bytecode;
+mod c;
+mod command;
+mod hack_asm;
+mod lowering;
+mod source_map;
+mod wat;
+mod x86_64;
+
+pub use bytecode::{read_bytecode, write_bytecode, BytecodeError, WriteBytecodeError};
+pub use command::{parse_vm, ArithOp, ParseVmError, Segment, VMCommand, VmInstr};
+pub use source_map::{SourceMap, SourceMapEntry};
+
+use hack_asm::{file_to_asm_code, make_bootstrap_code, LabelGenerator};
+
+/// A .vm file to translate
+#[derive(Debug, Clone)]
+pub struct VmFile {
+    /// File name without the `.vm` extension, used for static variables and label scopes
+    pub name: String,
+    pub source: VmSource,
+}
+impl VmFile {
+    /// Parse or decode the commands of this file
+    pub fn commands(&self) -> Result<Vec<VMCommand>, TranslateError> {
+        match &self.source {
+            VmSource::Text(text) => parse_vm(text).map_err(|err| TranslateError::Parse {
+                file: format!("{}.vm", self.name),
+                line: err.line,
+                message: err.message,
+            }),
+            VmSource::Bytecode(bytes) => {
+                read_bytecode(bytes).map_err(|err| TranslateError::Bytecode {
+                    file: format!("{}.vmb", self.name),
+                    message: err.to_string(),
+                })
+            }
+        }
+    }
+}
+
+/// Contents of a VM file
+#[derive(Debug, Clone)]
+pub enum VmSource {
+    /// A .vm text file
+    Text(String),
+    /// A .vmb file, see [`write_bytecode`]
+    Bytecode(Vec<u8>),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    /// Emit the bootstrap code which sets up the stack and calls `Sys.init`
+    pub bootstrap: bool,
+    pub target: Target,
+}
+
+/// What [`translate`] generates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Target {
+    /// Hack assembly for the nand2tetris CPU
+    #[default]
+    Hack,
+    /// GNU assembler code for x86-64 Linux, see the `x86_64` module
+    X86_64,
+    /// C source for GCC or Clang with a headless runtime, see the `c` module
+    C,
+    /// WebAssembly text format, see the `wat` module
+    Wat,
+}
+
+/// Translate all files of a program into one program for `options.target`
+pub fn translate(files: &[VmFile], options: Options) -> Result<String, TranslateError> {
+    match options.target {
+        Target::Hack => {
+            translate_with_source_map(files, options).map(|(assembler_code, _)| assembler_code)
+        }
+        Target::X86_64 => x86_64::translate(files, options),
+        Target::C => c::translate(files, options),
+        Target::Wat => wat::translate(files, options),
+    }
+}
+
+/// Translate into Hack assembly and also return the map from assembly lines back to
+/// VM lines. `options.target` is ignored
+pub fn translate_with_source_map(
+    files: &[VmFile],
+    options: Options,
+) -> Result<(String, SourceMap), TranslateError> {
+    // all files share one label generator so labels stay unique
+    let mut labels = LabelGenerator::default();
+    let mut source_map = SourceMap::default();
+
+    let mut final_assembler_code = String::new();
+    if options.bootstrap {
+        labels.define("bootstrap", "<bootstrap>", 0)?;
+        final_assembler_code += &make_bootstrap_code();
+    }
+
+    for (idx, file) in files.iter().enumerate() {
+        if idx > 0 {
+            final_assembler_code += "\n\n//NEW FILE!\n\n";
+        }
+        let (assembler_code, entries) = file_to_asm_code(file, &mut labels)?;
+        source_map.extend_at(entries, final_assembler_code.matches('\n').count());
+        final_assembler_code += &assembler_code;
+    }
+
+    Ok((final_assembler_code, source_map))
+}
+
+#[derive(Debug)]
+pub enum TranslateError {
+    Parse {
+        file: String,
+        line: usize,
+        message: String,
+    },
+    Bytecode {
+        file: String,
+        message: String,
+    },
+    /// A call or goto whose function or label is never defined
+    Undefined {
+        name: String,
+        file: String,
+        line: usize,
+    },
+    LabelCollision {
+        label: String,
+        first_file: String,
+        first_line: usize,
+        file: String,
+        line: usize,
+    },
+}
+impl std::fmt::Display for TranslateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TranslateError::Parse {
+                file,
+                line,
+                message,
+            } => write!(f, "{} line {}: {}", file, line, message),
+            TranslateError::Bytecode { file, message } => write!(f, "{}: {}", file, message),
+            TranslateError::Undefined { name, file, line } => {
+                write!(f, "{} line {}: {:?} is never defined", file, line, name)
+            }
+            TranslateError::LabelCollision {
+                label,
+                first_file,
+                first_line,
+                file,
+                line,
+            } => write!(
+                f,
+                "label {:?} defined in {} line {} was already defined in {} line {}",
+                label, file, line, first_file, first_line
+            ),
+        }
+    }
+}
+impl std::error::Error for TranslateError {}
@@ -0,0 +1,278 @@
+//! Lowering of VM files into a flat program for the native backends (x86-64, C, WebAssembly).
+//!
+//! Resolves everything the Hack assembler would otherwise do for us: functions and labels
+//! get numeric ids, static variables get fixed RAM addresses (from 16 upwards, like Hack
+//! variables) and every call site gets a return site number which is pushed instead of a
+//! ROM address. Return site 0 is reserved for the bootstrap call of `Sys.init`.
+
+use std::collections::HashMap;
+
+use crate::{ArithOp, Segment, TranslateError, VmFile, VmInstr};
+
+/// A RAM location used by push and pop
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Location {
+    Constant(u16),
+    /// `RAM[RAM[base] + offset]`, base is the address of LCL, ARG, THIS or THAT
+    Indirect {
+        base: u16,
+        offset: u16,
+    },
+    /// `RAM[address]`, used by temp, pointer and static
+    Direct(u16),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LoweredInstr {
+    Arith(ArithOp),
+    Push(Location),
+    Pop(Location),
+    Label(usize),
+    Goto(usize),
+    IfGoto(usize),
+    /// A `goto` to the label right in front of it, the entry of `Sys.halt` or a call of
+    /// `Sys.halt` in a program that does not define it
+    Halt,
+    Function {
+        id: usize,
+        nlocals: u16,
+    },
+    Call {
+        id: usize,
+        nargs: u16,
+        return_site: usize,
+    },
+    Return,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct LoweredCommand {
+    pub instr: LoweredInstr,
+    /// `File.vm:line: original instruction`, for comments in the generated code
+    pub origin: String,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct LoweredProgram {
+    pub commands: Vec<LoweredCommand>,
+    /// Function names by id
+    pub functions: Vec<String>,
+    /// Scoped label names (`function$label`) by id
+    pub labels: Vec<String>,
+    /// Number of call sites, return site numbers are `1..=return_sites`
+    pub return_sites: usize,
+    /// Id of `Sys.init` if the program starts with the bootstrap call
+    pub bootstrap: Option<usize>,
+}
+
+// RAM addresses of the virtual registers and segments
+pub(crate) const SP: u16 = 0;
+pub(crate) const LCL: u16 = 1;
+pub(crate) const ARG: u16 = 2;
+pub(crate) const THIS: u16 = 3;
+pub(crate) const THAT: u16 = 4;
+const TEMP: u16 = 5;
+const STATIC_START: u16 = 16;
+
+pub(crate) fn lower(files: &[VmFile], bootstrap: bool) -> Result<LoweredProgram, TranslateError> {
+    let mut functions: HashMap<String, usize> = HashMap::new();
+    let mut function_names: Vec<String> = vec![];
+    // function -> (file, line) of its definition
+    let mut defined_functions: HashMap<String, (String, usize)> = HashMap::new();
+    let mut labels: HashMap<String, usize> = HashMap::new();
+    let mut label_names: Vec<String> = vec![];
+    let mut defined_labels: HashMap<String, (String, usize)> = HashMap::new();
+    // (file, static index) -> RAM address
+    let mut statics: HashMap<(String, u16), u16> = HashMap::new();
+    let mut return_sites = 0;
+
+    // every reference of a function or label, checked once all files are known
+    let mut references: Vec<(bool, String, String, usize)> = vec![];
+
+    let mut function_id = |name: &str, functions: &mut HashMap<String, usize>| -> usize {
+        *functions.entry(name.to_string()).or_insert_with(|| {
+            function_names.push(name.to_string());
+            function_names.len() - 1
+        })
+    };
+    let mut label_id = |name: String, labels: &mut HashMap<String, usize>| -> usize {
+        *labels.entry(name.clone()).or_insert_with(|| {
+            label_names.push(name);
+            label_names.len() - 1
+        })
+    };
+
+    let mut commands = vec![];
+    let bootstrap = if bootstrap {
+        Some(function_id("Sys.init", &mut functions))
+    } else {
+        None
+    };
+    if bootstrap.is_some() {
+        references.push((true, "Sys.init".to_string(), "<bootstrap>".to_string(), 0));
+    }
+
+    let parsed_files = files
+        .iter()
+        .map(|file| Ok((file, file.commands()?)))
+        .collect::<Result<Vec<_>, TranslateError>>()?;
+    // Sys.halt never returns, so entering it halts whatever its body is (the OS one spins
+    // in a `while (true)` loop); a program translated without the OS may still call it
+    let defines_halt = parsed_files.iter().any(|(_, file_commands)| {
+        file_commands.iter().any(|command| {
+            matches!(&command.instr, VmInstr::Function { name, .. } if name == "Sys.halt")
+        })
+    });
+
+    for (file, file_commands) in parsed_files {
+        let file_name = format!("{}.vm", file.name);
+        // label scope: current function, or the file before the first function
+        let mut scope = file.name.clone();
+        let mut previous_label: Option<usize> = None;
+
+        for command in file_commands {
+            let origin = format!("{}:{}: {}", file_name, command.line, command.instr);
+            let location = |segment: Segment,
+                            index: u16,
+                            statics: &mut HashMap<(String, u16), u16>|
+             -> Result<Location, TranslateError> {
+                Ok(match segment {
+                    Segment::Constant => Location::Constant(index),
+                    Segment::Local => Location::Indirect {
+                        base: LCL,
+                        offset: index,
+                    },
+                    Segment::Argument => Location::Indirect {
+                        base: ARG,
+                        offset: index,
+                    },
+                    Segment::This => Location::Indirect {
+                        base: THIS,
+                        offset: index,
+                    },
+                    Segment::That => Location::Indirect {
+                        base: THAT,
+                        offset: index,
+                    },
+                    Segment::Temp => Location::Direct(TEMP + index),
+                    Segment::Pointer => Location::Direct(THIS + index),
+                    Segment::Static => {
+                        let next_address = STATIC_START + statics.len() as u16;
+                        let address = *statics
+                            .entry((file.name.clone(), index))
+                            .or_insert(next_address);
+                        if address >= 256 {
+                            return Err(TranslateError::Parse {
+                                file: file_name.clone(),
+                                line: command.line,
+                                message: "too many static variables, they would overlap the stack"
+                                    .to_string(),
+                            });
+                        }
+                        Location::Direct(address)
+                    }
+                })
+            };
+
+            let instr = match &command.instr {
+                VmInstr::Arith(op) => LoweredInstr::Arith(*op),
+                VmInstr::Push(segment, index) => {
+                    LoweredInstr::Push(location(*segment, *index, &mut statics)?)
+                }
+                VmInstr::Pop(segment, index) => {
+                    LoweredInstr::Pop(location(*segment, *index, &mut statics)?)
+                }
+                VmInstr::Label(label) => {
+                    let name = format!("{}${}", scope, label);
+                    if let Some((first_file, first_line)) = defined_labels.get(&name) {
+                        return Err(TranslateError::LabelCollision {
+                            label: name,
+                            first_file: first_file.clone(),
+                            first_line: *first_line,
+                            file: file_name,
+                            line: command.line,
+                        });
+                    }
+                    defined_labels.insert(name.clone(), (file_name.clone(), command.line));
+                    LoweredInstr::Label(label_id(name, &mut labels))
+                }
+                VmInstr::Goto(label) | VmInstr::IfGoto(label) => {
+                    let name = format!("{}${}", scope, label);
+                    references.push((false, name.clone(), file_name.clone(), command.line));
+                    let id = label_id(name, &mut labels);
+                    match command.instr {
+                        VmInstr::Goto(_) if previous_label == Some(id) => LoweredInstr::Halt,
+                        VmInstr::Goto(_) => LoweredInstr::Goto(id),
+                        _ => LoweredInstr::IfGoto(id),
+                    }
+                }
+                VmInstr::Function { name, nlocals } => {
+                    if let Some((first_file, first_line)) = defined_functions.get(name) {
+                        return Err(TranslateError::LabelCollision {
+                            label: name.clone(),
+                            first_file: first_file.clone(),
+                            first_line: *first_line,
+                            file: file_name,
+                            line: command.line,
+                        });
+                    }
+                    defined_functions.insert(name.clone(), (file_name.clone(), command.line));
+                    scope = name.clone();
+                    LoweredInstr::Function {
+                        id: function_id(name, &mut functions),
+                        nlocals: *nlocals,
+                    }
+                }
+                VmInstr::Call { name, .. } if name == "Sys.halt" && !defines_halt => {
+                    LoweredInstr::Halt
+                }
+                VmInstr::Call { name, nargs } => {
+                    references.push((true, name.clone(), file_name.clone(), command.line));
+                    return_sites += 1;
+                    LoweredInstr::Call {
+                        id: function_id(name, &mut functions),
+                        nargs: *nargs,
+                        return_site: return_sites,
+                    }
+                }
+                VmInstr::Return => LoweredInstr::Return,
+            };
+
+            previous_label = match instr {
+                LoweredInstr::Label(id) => Some(id),
+                _ => None,
+            };
+            let enters_halt =
+                matches!(&command.instr, VmInstr::Function { name, .. } if name == "Sys.halt");
+            commands.push(LoweredCommand {
+                instr,
+                origin: origin.clone(),
+            });
+            if enters_halt {
+                commands.push(LoweredCommand {
+                    instr: LoweredInstr::Halt,
+                    origin,
+                });
+            }
+        }
+    }
+
+    for (is_function, name, file, line) in references {
+        let defined = if is_function {
+            defined_functions.contains_key(&name)
+        } else {
+            defined_labels.contains_key(&name)
+        };
+        if !defined {
+            return Err(TranslateError::Undefined { name, file, line });
+        }
+    }
+
+    Ok(LoweredProgram {
+        commands,
+        functions: function_names,
+        labels: label_names,
+        return_sites,
+        bootstrap,
+    })
+}
@@ -0,0 +1,37 @@
+/// The assembly lines generated for one VM instruction
+#[derive(Debug, Clone)]
+pub struct SourceMapEntry {
+    pub asm_start: usize,
+    pub asm_end: usize,
+    pub vm_file: String,
+    pub vm_line: usize,
+    pub function: String,
+}
+
+/// Maps assembly line ranges (1-based, inclusive) back to the VM file, line and function
+/// they were generated from. Written next to the .asm file as `<name>.asm.map`
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    pub entries: Vec<SourceMapEntry>,
+}
+impl SourceMap {
+    /// Add entries of a file whose code starts after `line_offset` lines of the .asm file
+    pub(crate) fn extend_at(&mut self, entries: Vec<SourceMapEntry>, line_offset: usize) {
+        self.entries
+            .extend(entries.into_iter().map(|entry| SourceMapEntry {
+                asm_start: entry.asm_start + line_offset,
+                asm_end: entry.asm_end + line_offset,
+                ..entry
+            }));
+    }
+    pub fn to_map_string(&self) -> String {
+        let mut buf = vec!["// asm_start asm_end vm_file vm_line function".to_string()];
+        for entry in &self.entries {
+            buf.push(format!(
+                "{} {} {} {} {}",
+                entry.asm_start, entry.asm_end, entry.vm_file, entry.vm_line, entry.function
+            ));
+        }
+        buf.join("\n") + "\n"
+    }
+}
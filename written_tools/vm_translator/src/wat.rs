@@ -0,0 +1,267 @@
+//! WebAssembly backend: one WAT module, RAM lives in linear memory.
+//!
+//! RAM word `a` is the 16 bit value at byte `2 * a` of the exported `memory`, so a host
+//! can read SCREEN at byte 32768 after the run. Every VM function becomes a wasm
+//! function and VM calls become wasm calls (the frame is still pushed to RAM, with the
+//! call site number as return address). Labels inside a function are resolved by a
+//! `$pc` dispatch loop over nested blocks, one block per label.
+//!
+//! The exported `run` function executes the program and returns the value on top of
+//! the stack once `Sys.init` returns or halts.
+
+use std::collections::HashMap;
+
+use crate::{
+    lowering::{
+        lower, Location, LoweredCommand, LoweredInstr, LoweredProgram, ARG, LCL, SP, THAT, THIS,
+    },
+    ArithOp, Options, TranslateError, VmFile,
+};
+
+pub(crate) fn translate(files: &[VmFile], options: Options) -> Result<String, TranslateError> {
+    let program = lower(files, options.bootstrap)?;
+    Ok(emit(&program))
+}
+
+const PRELUDE: &str = r#";; Generated by the Jack VM Translator
+(module
+  ;; 32K words of RAM, addresses wrap like on the Hack computer
+  (memory (export "memory") 1)
+  (global $halted (mut i32) (i32.const 0))
+
+  (func $load (param $address i32) (result i32)
+    (i32.load16_s (i32.shl (i32.and (local.get $address) (i32.const 32767)) (i32.const 1))))
+  (func $store (param $address i32) (param $value i32)
+    (i32.store16 (i32.shl (i32.and (local.get $address) (i32.const 32767)) (i32.const 1))
+      (local.get $value)))
+  (func $push (param $value i32)
+    (call $store (call $load (i32.const 0)) (local.get $value))
+    (call $store (i32.const 0) (i32.add (call $load (i32.const 0)) (i32.const 1))))
+  (func $pop (result i32)
+    (call $store (i32.const 0) (i32.sub (call $load (i32.const 0)) (i32.const 1)))
+    (call $load (call $load (i32.const 0))))
+"#;
+
+/// Commands of one wasm function: the top-level code or one VM function
+struct Unit<'a> {
+    /// Function id, `None` for the code in front of the first function
+    id: Option<usize>,
+    nlocals: u16,
+    commands: &'a [LoweredCommand],
+}
+
+fn units(program: &LoweredProgram) -> Vec<Unit<'_>> {
+    let mut units = vec![];
+    let mut start = 0;
+    let mut current = (None, 0);
+    for (idx, command) in program.commands.iter().enumerate() {
+        if let LoweredInstr::Function { id, nlocals } = command.instr {
+            units.push(Unit {
+                id: current.0,
+                nlocals: current.1,
+                commands: &program.commands[start..idx],
+            });
+            start = idx + 1;
+            current = (Some(id), nlocals);
+        }
+    }
+    units.push(Unit {
+        id: current.0,
+        nlocals: current.1,
+        commands: &program.commands[start..],
+    });
+    units
+}
+
+fn load(address: u16) -> String {
+    format!("(call $load (i32.const {}))", address)
+}
+
+/// Address of a non constant location
+fn address(location: &Location) -> String {
+    match location {
+        Location::Constant(_) => unreachable!("constants have no address"),
+        Location::Indirect { base, offset } => {
+            format!("(i32.add {} (i32.const {}))", load(*base), offset)
+        }
+        Location::Direct(address) => format!("(i32.const {})", address),
+    }
+}
+
+fn emit(program: &LoweredProgram) -> String {
+    let mut buf: Vec<String> = vec![PRELUDE.to_string()];
+
+    for unit in units(program) {
+        emit_unit(&mut buf, program, &unit);
+    }
+
+    buf.push("  (func (export \"run\") (result i32)".to_string());
+    buf.push(format!(
+        "    (call $store (i32.const {}) (i32.const 256))",
+        SP
+    ));
+    match program.bootstrap {
+        Some(sys_init) => {
+            buf.push("    ;; bootstrap: call Sys.init 0".to_string());
+            for register in [LCL, ARG, THIS, THAT] {
+                buf.push(format!(
+                    "    (call $store (i32.const {}) (i32.const -1))",
+                    register
+                ));
+            }
+            emit_call(&mut buf, sys_init, 0, 0);
+        }
+        None => buf.push("    (call $start)".to_string()),
+    }
+    buf.push(format!(
+        "    (call $load (i32.sub {} (i32.const 1))))",
+        load(SP)
+    ));
+    buf.push(")".to_string());
+
+    buf.join("\n") + "\n"
+}
+
+fn emit_unit(buf: &mut Vec<String>, program: &LoweredProgram, unit: &Unit) {
+    match unit.id {
+        Some(id) => buf.push(format!("  (func $f{} ;; {}", id, program.functions[id])),
+        None => buf.push("  (func $start ;; code in front of the first function".to_string()),
+    }
+    buf.push("    (local $pc i32) (local $y i32) (local $frame i32)".to_string());
+    for _ in 0..unit.nlocals {
+        buf.push("    (call $push (i32.const 0))".to_string());
+    }
+
+    // label id -> block number, block n ends right in front of the code of label n + 1
+    let label_blocks: HashMap<usize, usize> = unit
+        .commands
+        .iter()
+        .filter_map(|command| match command.instr {
+            LoweredInstr::Label(id) => Some(id),
+            _ => None,
+        })
+        .enumerate()
+        .map(|(block, id)| (id, block + 1))
+        .collect();
+    let blocks = label_blocks.len() + 1;
+
+    buf.push("    (loop $dispatch".to_string());
+    for block in (0..blocks).rev() {
+        buf.push(format!("    (block $b{}", block));
+    }
+    let targets: Vec<String> = (0..blocks).map(|block| format!("$b{}", block)).collect();
+    buf.push(format!(
+        "      (br_table {} (local.get $pc)))",
+        targets.join(" ")
+    ));
+
+    let jump = |label: &usize| {
+        format!(
+            "(local.set $pc (i32.const {})) (br $dispatch)",
+            label_blocks[label]
+        )
+    };
+    for command in unit.commands {
+        buf.push(format!("      ;; {}", command.origin));
+        match &command.instr {
+            LoweredInstr::Arith(op) => buf.push(format!("      {}", arith(*op))),
+            LoweredInstr::Push(Location::Constant(value)) => {
+                buf.push(format!("      (call $push (i32.const {}))", value))
+            }
+            LoweredInstr::Push(location) => buf.push(format!(
+                "      (call $push (call $load {}))",
+                address(location)
+            )),
+            LoweredInstr::Pop(location) => buf.push(format!(
+                "      (call $store {} (call $pop))",
+                address(location)
+            )),
+            LoweredInstr::Label(id) => {
+                buf.push(format!(
+                    "      ) ;; end $b{}, label {}",
+                    label_blocks[id], program.labels[*id]
+                ));
+            }
+            LoweredInstr::Goto(id) => buf.push(format!("      {}", jump(id))),
+            LoweredInstr::IfGoto(id) => {
+                buf.push(format!("      (if (call $pop) (then {}))", jump(id)));
+            }
+            LoweredInstr::Halt => {
+                buf.push("      (global.set $halted (i32.const 1)) (return)".to_string())
+            }
+            LoweredInstr::Function { .. } => unreachable!("functions start a new unit"),
+            LoweredInstr::Call {
+                id,
+                nargs,
+                return_site,
+            } => {
+                emit_call(buf, *id, *nargs, *return_site);
+                buf.push("      (if (global.get $halted) (then (return)))".to_string());
+            }
+            LoweredInstr::Return => emit_return(buf),
+        }
+    }
+    buf.push("    ) ;; end $dispatch".to_string());
+    buf.push("  )".to_string());
+    buf.push("".to_string());
+}
+
+fn arith(op: ArithOp) -> String {
+    let x = "(call $pop)";
+    let result = match op {
+        ArithOp::Neg => format!("(i32.sub (i32.const 0) {})", x),
+        ArithOp::Not => format!("(i32.xor {} (i32.const -1))", x),
+        _ => {
+            let y = "(local.get $y)";
+            let result = match op {
+                ArithOp::Add => format!("(i32.add {} {})", x, y),
+                ArithOp::Sub => format!("(i32.sub {} {})", x, y),
+                ArithOp::And => format!("(i32.and {} {})", x, y),
+                ArithOp::Or => format!("(i32.or {} {})", x, y),
+                // true is -1
+                ArithOp::Eq => format!("(i32.sub (i32.const 0) (i32.eq {} {}))", x, y),
+                ArithOp::Gt => format!("(i32.sub (i32.const 0) (i32.gt_s {} {}))", x, y),
+                ArithOp::Lt => format!("(i32.sub (i32.const 0) (i32.lt_s {} {}))", x, y),
+                ArithOp::Neg | ArithOp::Not => unreachable!(),
+            };
+            return format!("(local.set $y (call $pop)) (call $push {})", result);
+        }
+    };
+    format!("(call $push {})", result)
+}
+
+fn emit_call(buf: &mut Vec<String>, id: usize, nargs: u16, return_site: usize) {
+    buf.push(format!("      (call $push (i32.const {}))", return_site));
+    for register in [LCL, ARG, THIS, THAT] {
+        buf.push(format!("      (call $push {})", load(register)));
+    }
+    buf.push(format!(
+        "      (call $store (i32.const {}) (i32.sub {} (i32.const {})))",
+        ARG,
+        load(SP),
+        5 + nargs as u32
+    ));
+    buf.push(format!(
+        "      (call $store (i32.const {}) {})",
+        LCL,
+        load(SP)
+    ));
+    buf.push(format!("      (call $f{})", id));
+}
+
+fn emit_return(buf: &mut Vec<String>) {
+    buf.push(format!("      (local.set $frame {})", load(LCL)));
+    buf.push(format!("      (call $store {} (call $pop))", load(ARG)));
+    buf.push(format!(
+        "      (call $store (i32.const {}) (i32.add {} (i32.const 1)))",
+        SP,
+        load(ARG)
+    ));
+    for (distance, register) in [(1, THAT), (2, THIS), (3, ARG), (4, LCL)] {
+        buf.push(format!(
+            "      (call $store (i32.const {}) (call $load (i32.sub (local.get $frame) (i32.const {}))))",
+            register, distance
+        ));
+    }
+    buf.push("      (return)".to_string());
+}
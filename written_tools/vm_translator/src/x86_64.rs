@@ -0,0 +1,247 @@
+//! x86-64 backend: GNU assembler code for Linux implementing the VM stack machine.
+//!
+//! The generated program contains its own tiny runtime, no C library is needed:
+//!
+//! ```text
+//! as -o Prog.o Prog.s && ld -o Prog Prog.o && ./Prog; echo $?
+//! ```
+//!
+//! The Hack memory is modelled as `hack_ram` (SP, LCL, ARG, THIS and THAT live at
+//! RAM[0..5] like on the Hack computer), with `hack_screen` and `hack_kbd` pointing at
+//! the memory mapped SCREEN and KBD areas. `%rbx` holds the RAM base for the whole run.
+//! The program exits once `Sys.init` returns or halts (a label that jumps to itself or
+//! entering `Sys.halt`, which does not need to be defined); the exit status is the low
+//! byte of the value on top of the stack.
+
+use crate::{
+    lowering::{lower, Location, LoweredInstr, LoweredProgram, ARG, LCL, SP, THAT, THIS},
+    ArithOp, Options, TranslateError, VmFile,
+};
+
+pub(crate) fn translate(files: &[VmFile], options: Options) -> Result<String, TranslateError> {
+    let program = lower(files, options.bootstrap)?;
+    Ok(emit(&program))
+}
+
+/// Byte offset of a RAM word relative to `%rbx`
+fn ram(address: u16) -> String {
+    format!("{}(%rbx)", address as u32 * 2)
+}
+
+fn emit(program: &LoweredProgram) -> String {
+    let mut buf: Vec<String> = vec![
+        "# Generated by the Jack VM Translator".to_string(),
+        "    .bss".to_string(),
+        "    .align 16".to_string(),
+        "    .globl hack_ram, hack_screen, hack_kbd".to_string(),
+        // 64K words so that every 16 bit address is in bounds
+        "hack_ram:".to_string(),
+        "    .zero 131072".to_string(),
+        "    .set hack_screen, hack_ram + 16384 * 2".to_string(),
+        "    .set hack_kbd, hack_ram + 24576 * 2".to_string(),
+        "".to_string(),
+        "    .text".to_string(),
+        "    .globl _start".to_string(),
+        "_start:".to_string(),
+        "    leaq hack_ram(%rip), %rbx".to_string(),
+        format!("    movw $256, {} # SP = 256", ram(SP)),
+    ];
+
+    if let Some(sys_init) = program.bootstrap {
+        buf.push("# bootstrap: call Sys.init 0".to_string());
+        for register in [LCL, ARG, THIS, THAT] {
+            buf.push(format!("    movw $-1, {}", ram(register)));
+        }
+        emit_call(&mut buf, sys_init, 0, 0);
+    }
+
+    for command in &program.commands {
+        buf.push(format!("# {}", command.origin));
+        match &command.instr {
+            LoweredInstr::Arith(op) => emit_arith(&mut buf, *op),
+            LoweredInstr::Push(location) => {
+                match location {
+                    Location::Constant(value) => buf.push(format!("    movw ${}, %ax", value)),
+                    Location::Indirect { base, offset } => {
+                        emit_indirect_address(&mut buf, *base, *offset);
+                        buf.push("    movw (%rbx,%rdx,2), %ax".to_string());
+                    }
+                    Location::Direct(address) => {
+                        buf.push(format!("    movw {}, %ax", ram(*address)))
+                    }
+                }
+                emit_push_ax(&mut buf);
+            }
+            LoweredInstr::Pop(location) => match location {
+                Location::Constant(_) => unreachable!("pop constant is not possible"),
+                Location::Indirect { base, offset } => {
+                    emit_indirect_address(&mut buf, *base, *offset);
+                    emit_pop_ax(&mut buf);
+                    buf.push("    movw %ax, (%rbx,%rdx,2)".to_string());
+                }
+                Location::Direct(address) => {
+                    emit_pop_ax(&mut buf);
+                    buf.push(format!("    movw %ax, {}", ram(*address)));
+                }
+            },
+            LoweredInstr::Label(id) => {
+                buf.push(format!("vm_l{}: # {}", id, program.labels[*id]));
+            }
+            LoweredInstr::Goto(id) => buf.push(format!("    jmp vm_l{}", id)),
+            LoweredInstr::IfGoto(id) => {
+                emit_pop_ax(&mut buf);
+                buf.push("    testw %ax, %ax".to_string());
+                buf.push(format!("    jnz vm_l{}", id));
+            }
+            LoweredInstr::Halt => buf.push("    jmp vm_exit".to_string()),
+            LoweredInstr::Function { id, nlocals } => {
+                buf.push(format!("vm_f{}: # {}", id, program.functions[*id]));
+                if *nlocals > 0 {
+                    buf.push(format!("    movzwl {}, %ecx", ram(SP)));
+                    for local in 0..*nlocals as u32 {
+                        buf.push(format!("    movw $0, {}(%rbx,%rcx,2)", local * 2));
+                    }
+                    buf.push(format!("    addw ${}, {}", nlocals, ram(SP)));
+                }
+            }
+            LoweredInstr::Call {
+                id,
+                nargs,
+                return_site,
+            } => emit_call(&mut buf, *id, *nargs, *return_site),
+            LoweredInstr::Return => emit_return(&mut buf, program.return_sites),
+        }
+    }
+
+    buf.extend(
+        [
+            "# end of program",
+            "    jmp vm_exit",
+            "",
+            "# exit with the low byte of the top of the stack as status",
+            "vm_exit:",
+            "    movzwl (%rbx), %ecx",
+            "    movzwl -2(%rbx,%rcx,2), %edi",
+            "    andl $255, %edi",
+            "    movl $60, %eax # exit",
+            "    syscall",
+            "",
+            "# return to an address which was never pushed by a call",
+            "vm_bad_return:",
+            "    ud2",
+            "",
+            "    .section .rodata",
+            "    .align 8",
+            "vm_return_table:",
+            "    .quad vm_exit",
+        ]
+        .map(str::to_string),
+    );
+    for return_site in 1..=program.return_sites {
+        buf.push(format!("    .quad vm_r{}", return_site));
+    }
+
+    buf.join("\n") + "\n"
+}
+
+/// `%edx` = `RAM[base] + offset`, wrapped to 16 bit
+fn emit_indirect_address(buf: &mut Vec<String>, base: u16, offset: u16) {
+    buf.push(format!("    movzwl {}, %edx", ram(base)));
+    if offset != 0 {
+        buf.push(format!("    addl ${}, %edx", offset));
+        buf.push("    movzwl %dx, %edx".to_string());
+    }
+}
+
+fn emit_push_ax(buf: &mut Vec<String>) {
+    buf.push(format!("    movzwl {}, %ecx", ram(SP)));
+    buf.push("    movw %ax, (%rbx,%rcx,2)".to_string());
+    buf.push(format!("    incw {}", ram(SP)));
+}
+
+fn emit_pop_ax(buf: &mut Vec<String>) {
+    buf.push(format!("    decw {}", ram(SP)));
+    buf.push(format!("    movzwl {}, %ecx", ram(SP)));
+    buf.push("    movw (%rbx,%rcx,2), %ax".to_string());
+}
+
+fn emit_arith(buf: &mut Vec<String>, op: ArithOp) {
+    match op {
+        ArithOp::Neg | ArithOp::Not => {
+            buf.push(format!("    movzwl {}, %ecx", ram(SP)));
+            let instr = if op == ArithOp::Neg { "negw" } else { "notw" };
+            buf.push(format!("    {} -2(%rbx,%rcx,2)", instr));
+        }
+        ArithOp::Add | ArithOp::Sub | ArithOp::And | ArithOp::Or => {
+            // y in %ax, x stays on the stack and is updated in place
+            emit_pop_ax(buf);
+            let instr = match op {
+                ArithOp::Add => "addw",
+                ArithOp::Sub => "subw",
+                ArithOp::And => "andw",
+                _ => "orw",
+            };
+            buf.push(format!("    {} %ax, -2(%rbx,%rcx,2)", instr));
+        }
+        ArithOp::Eq | ArithOp::Gt | ArithOp::Lt => {
+            emit_pop_ax(buf);
+            // flags of x - y, the signed conditions do not overflow
+            buf.push("    cmpw %ax, -2(%rbx,%rcx,2)".to_string());
+            let set = match op {
+                ArithOp::Eq => "sete",
+                ArithOp::Gt => "setg",
+                _ => "setl",
+            };
+            buf.push(format!("    {} %al", set));
+            // 1 -> -1 (true), 0 -> 0 (false)
+            buf.push("    movzbw %al, %ax".to_string());
+            buf.push("    negw %ax".to_string());
+            buf.push("    movw %ax, -2(%rbx,%rcx,2)".to_string());
+        }
+    }
+}
+
+fn emit_call(buf: &mut Vec<String>, id: usize, nargs: u16, return_site: usize) {
+    buf.push(format!("    movw ${}, %ax # return site", return_site));
+    emit_push_ax(buf);
+    for register in [LCL, ARG, THIS, THAT] {
+        buf.push(format!("    movw {}, %ax", ram(register)));
+        emit_push_ax(buf);
+    }
+    // ARG = SP - 5 - nargs
+    buf.push(format!("    movw {}, %ax", ram(SP)));
+    buf.push(format!("    subw ${}, %ax", 5 + nargs as u32));
+    buf.push(format!("    movw %ax, {}", ram(ARG)));
+    // LCL = SP
+    buf.push(format!("    movw {}, %ax", ram(SP)));
+    buf.push(format!("    movw %ax, {}", ram(LCL)));
+    buf.push(format!("    jmp vm_f{}", id));
+    if return_site != 0 {
+        buf.push(format!("vm_r{}:", return_site));
+    }
+}
+
+fn emit_return(buf: &mut Vec<String>, return_sites: usize) {
+    // endFrame in %esi, return site in %edi
+    buf.push(format!("    movzwl {}, %esi", ram(LCL)));
+    buf.push("    leal -5(%rsi), %eax".to_string());
+    buf.push("    movzwl %ax, %eax".to_string());
+    buf.push("    movzwl (%rbx,%rax,2), %edi".to_string());
+    // *ARG = pop(), SP = ARG + 1
+    emit_pop_ax(buf);
+    buf.push(format!("    movzwl {}, %ecx", ram(ARG)));
+    buf.push("    movw %ax, (%rbx,%rcx,2)".to_string());
+    buf.push("    leal 1(%rcx), %eax".to_string());
+    buf.push(format!("    movw %ax, {}", ram(SP)));
+    // restore THAT, THIS, ARG and LCL of the caller
+    for (distance, register) in [(1, THAT), (2, THIS), (3, ARG), (4, LCL)] {
+        buf.push(format!("    leal -{}(%rsi), %eax", distance));
+        buf.push("    movzwl %ax, %eax".to_string());
+        buf.push("    movw (%rbx,%rax,2), %ax".to_string());
+        buf.push(format!("    movw %ax, {}", ram(register)));
+    }
+    buf.push(format!("    cmpl ${}, %edi", return_sites));
+    buf.push("    ja vm_bad_return".to_string());
+    buf.push("    leaq vm_return_table(%rip), %rdx".to_string());
+    buf.push("    jmp *(%rdx,%rdi,8)".to_string());
+}
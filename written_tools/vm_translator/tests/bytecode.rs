@@ -0,0 +1,113 @@
+//! `.vmb` files: everything written reads back the same and malformed input is rejected
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use vm_translator::{parse_vm, read_bytecode, write_bytecode, VMCommand, VmInstr};
+
+fn vm_files(dir: &Path, found: &mut Vec<PathBuf>) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            vm_files(&path, found);
+        } else if path.extension().is_some_and(|ext| ext == "vm") {
+            found.push(path);
+        }
+    }
+}
+
+#[test]
+fn round_trip_of_project_08() {
+    let mut files = vec![];
+    vm_files(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("../../projects/08"),
+        &mut files,
+    );
+    assert!(!files.is_empty());
+
+    for file in files {
+        let commands = parse_vm(&fs::read_to_string(&file).unwrap()).unwrap();
+        let bytes = write_bytecode(&commands).unwrap();
+        let read_back = read_bytecode(&bytes).unwrap();
+        let instrs = |commands: &[VMCommand]| -> Vec<VmInstr> {
+            commands
+                .iter()
+                .map(|command| command.instr.clone())
+                .collect()
+        };
+        assert_eq!(instrs(&read_back), instrs(&commands), "{}", file.display());
+    }
+}
+
+/// A .vmb file with the given symbols, no constants and one instruction
+fn one_instruction(symbols: &[&str], instruction: &[u8]) -> Vec<u8> {
+    let mut bytes = b"HVMB\x01".to_vec();
+    bytes.extend((symbols.len() as u16).to_le_bytes());
+    for symbol in symbols {
+        bytes.extend((symbol.len() as u16).to_le_bytes());
+        bytes.extend(symbol.as_bytes());
+    }
+    bytes.extend(0u16.to_le_bytes());
+    bytes.extend(1u32.to_le_bytes());
+    bytes.extend(instruction);
+    bytes
+}
+
+fn error_of(bytes: &[u8]) -> String {
+    read_bytecode(bytes).unwrap_err().message
+}
+
+#[test]
+fn counts_above_32767() {
+    assert_eq!(
+        error_of(&one_instruction(&["Main.main"], &[0x30, 0, 0, 0x40, 0x9c])),
+        "nlocals 40000 out of range 0..32767"
+    );
+    assert_eq!(
+        error_of(&one_instruction(&["Main.main"], &[0x31, 0, 0, 0x00, 0x80])),
+        "nargs 32768 out of range 0..32767"
+    );
+    assert_eq!(
+        error_of(&one_instruction(&[], &[0x11, 0, 0x00, 0x80])),
+        "local 32768 out of range 0..32767"
+    );
+}
+
+#[test]
+fn table_indices_out_of_range() {
+    assert_eq!(
+        error_of(&one_instruction(&["Main.main"], &[0x31, 1, 0, 0, 0])),
+        "symbol 1 out of range"
+    );
+    assert_eq!(
+        error_of(&one_instruction(&[], &[0x10, 6, 0, 0])),
+        "constant 0 out of range"
+    );
+}
+
+#[test]
+fn names_no_vm_line_can_hold() {
+    assert_eq!(
+        error_of(&one_instruction(&["Main main"], &[0x30, 0, 0, 0, 0])),
+        "function name \"Main main\" is empty or contains whitespace"
+    );
+    assert_eq!(
+        error_of(&one_instruction(&[""], &[0x31, 0, 0, 0, 0])),
+        "function name \"\" is empty or contains whitespace"
+    );
+    assert_eq!(
+        error_of(&one_instruction(&["a b"], &[0x20, 0, 0])),
+        "label \"a b\" may only use letters, digits, _, . and : and not start with a digit"
+    );
+}
+
+#[test]
+fn truncated_input() {
+    let bytes = one_instruction(&["Main.main"], &[0x30, 0, 0, 2, 0]);
+    for len in 0..bytes.len() {
+        assert!(read_bytecode(&bytes[..len]).is_err(), "{} bytes", len);
+    }
+    assert!(read_bytecode(&bytes).is_ok());
+}
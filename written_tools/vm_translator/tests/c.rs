@@ -0,0 +1,71 @@
+//! The C backend: programs are compiled with `cc` and run headless, the exit status is
+//! the low byte of the top of the stack and the screen is dumped as PBM
+
+mod common;
+
+use std::{fs, process::Command};
+
+use common::{build, has_tool, load, program, run, scratch_dir};
+use vm_translator::{translate, Options, Target, VmFile};
+
+/// Exit status and the PBM screen dump, `None` if `cc` is missing
+fn run_program(test: &str, files: &[VmFile]) -> Option<(i32, Vec<u8>)> {
+    if !has_tool("cc") {
+        eprintln!("skipping {}: cc not found", test);
+        return None;
+    }
+    let options = Options {
+        bootstrap: true,
+        target: Target::C,
+    };
+    let dir = scratch_dir(test);
+    fs::write(dir.join("Prog.c"), translate(files, options).unwrap()).unwrap();
+    build(
+        Command::new("cc")
+            .current_dir(&dir)
+            .args(["-O1", "-o", "Prog", "Prog.c"]),
+    );
+    let status = run(Command::new(dir.join("Prog")).arg(dir.join("screen.pbm")));
+    Some((status, fs::read(dir.join("screen.pbm")).unwrap()))
+}
+
+#[test]
+fn halts_in_a_label_jumping_to_itself() {
+    let files = load(&["projects/08/FunctionCalls/FibonacciElement"]);
+    if let Some((status, _)) = run_program("fibonacci_element", &files) {
+        assert_eq!(status, 3);
+    }
+}
+
+#[test]
+fn halts_on_sys_halt_without_the_os() {
+    let files = program(
+        "Sys",
+        "function Sys.init 0
+push constant 42
+call Sys.halt 0
+push constant 1
+return",
+    );
+    if let Some((status, _)) = run_program("sys_halt_without_os", &files) {
+        assert_eq!(status, 42);
+    }
+}
+
+#[test]
+fn runs_a_program_with_the_os_to_its_end() {
+    // prints 1 + 2 * 3, then the OS Sys.init calls Sys.halt
+    let files = load(&["projects/11/Seven", "tools/OS"]);
+    if let Some((_, screen)) = run_program("seven_with_os", &files) {
+        let header = b"P4\n512 256\n";
+        assert_eq!(&screen[..header.len()], header);
+        // the glyph of `7` in the top left corner below the blank first row of the
+        // character cell, PBM rows are 64 bytes
+        let seven: [u8; 11] = [63, 49, 48, 48, 24, 12, 12, 12, 12, 0, 0];
+        let mut expected = vec![0; 64 * 256];
+        for (row, bits) in seven.iter().enumerate() {
+            expected[(row + 1) * 64] = bits.reverse_bits();
+        }
+        assert!(screen[header.len()..] == expected[..]);
+    }
+}
@@ -0,0 +1,98 @@
+//! Helpers for the tests which build and run the output of the native backends, every
+//! test crate only uses some of them
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use vm_translator::{VmFile, VmSource};
+
+/// The .vm files of directories relative to the repository root
+pub fn load(dirs: &[&str]) -> Vec<VmFile> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../..");
+    let mut paths: Vec<PathBuf> = dirs
+        .iter()
+        .flat_map(|dir| fs::read_dir(root.join(dir)).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "vm"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| VmFile {
+            name: path.file_stem().unwrap().to_str().unwrap().to_string(),
+            source: VmSource::Text(fs::read_to_string(path).unwrap()),
+        })
+        .collect()
+}
+
+/// One VM file given as text
+pub fn program(name: &str, source: &str) -> Vec<VmFile> {
+    vec![VmFile {
+        name: name.to_string(),
+        source: VmSource::Text(source.to_string()),
+    }]
+}
+
+/// Tests skip themselves if a tool is missing
+pub fn has_tool(name: &str) -> bool {
+    Command::new(name).arg("--version").output().is_ok()
+}
+
+/// Empty directory for the files of one test
+pub fn scratch_dir(test: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(test);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Run a build step which has to succeed
+pub fn build(command: &mut Command) {
+    let output = command.output().unwrap();
+    assert!(
+        output.status.success(),
+        "{:?} failed:\n{}",
+        command,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Run a translated program to its end and return its exit status, a program which does
+/// not halt within 10 seconds fails the test
+pub fn run(command: &mut Command) -> i32 {
+    run_with_output(command).0
+}
+
+/// [`run`] which also returns what the program wrote to stdout
+pub fn run_with_output(command: &mut Command) -> (i32, String) {
+    let mut child = command.stdout(Stdio::piped()).spawn().unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            let code = status
+                .code()
+                .unwrap_or_else(|| panic!("{:?} was killed: {}", command, status));
+            let mut stdout = String::new();
+            child
+                .stdout
+                .take()
+                .unwrap()
+                .read_to_string(&mut stdout)
+                .unwrap();
+            return (code, stdout);
+        }
+        if Instant::now() > deadline {
+            child.kill().unwrap();
+            child.wait().unwrap();
+            panic!("{:?} did not halt within 10 seconds", command);
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,88 @@
+//! Labels of the Hack assembly backend: user labels never meet generated ones and real
+//! collisions are reported
+
+use vm_translator::{translate, Options, TranslateError, VmFile, VmSource};
+
+fn program(files: &[(&str, &str)]) -> Vec<VmFile> {
+    files
+        .iter()
+        .map(|(name, source)| VmFile {
+            name: name.to_string(),
+            source: VmSource::Text(source.to_string()),
+        })
+        .collect()
+}
+
+#[test]
+fn user_labels_named_like_generated_ones() {
+    let files = program(&[(
+        "Main",
+        "function Main.main 0
+label ret.0
+label _COMP_LABEL_TRUE
+call Main.main 0
+push constant 1
+push constant 2
+lt
+goto ret.0",
+    )]);
+    let asm = translate(&files, Options::default()).unwrap();
+    assert!(asm.contains("(Main.main$ret.0)"));
+    assert!(asm.contains("(Main.main$ret$0)"));
+    assert!(asm.contains("(Main.main$_COMP_LABEL_TRUE)"));
+}
+
+#[test]
+fn same_label_in_two_functions() {
+    let files = program(&[
+        ("Main", "function Main.main 0\nlabel LOOP\ngoto LOOP"),
+        ("Other", "function Other.run 0\nlabel LOOP\ngoto LOOP"),
+    ]);
+    assert!(translate(&files, Options::default()).is_ok());
+}
+
+#[test]
+fn label_defined_twice_in_one_function() {
+    let files = program(&[("Main", "function Main.main 0\nlabel LOOP\nlabel LOOP")]);
+    match translate(&files, Options::default()) {
+        Err(TranslateError::LabelCollision {
+            label,
+            first_line,
+            line,
+            ..
+        }) => {
+            assert_eq!(label, "Main.main$LOOP");
+            assert_eq!((first_line, line), (2, 3));
+        }
+        other => panic!("expected a label collision, got {:?}", other),
+    }
+}
+
+#[test]
+fn function_defined_in_two_files() {
+    let files = program(&[
+        ("Main", "function Main.main 0\nreturn"),
+        ("Copy", "function Main.main 0\nreturn"),
+    ]);
+    match translate(&files, Options::default()) {
+        Err(TranslateError::LabelCollision {
+            first_file, file, ..
+        }) => assert_eq!((first_file.as_str(), file.as_str()), ("Main.vm", "Copy.vm")),
+        other => panic!("expected a label collision, got {:?}", other),
+    }
+}
+
+#[test]
+fn labels_outside_the_vm_label_syntax() {
+    for label in ["ret$0", "0start", "a-b"] {
+        let files = program(&[("Main", &format!("function Main.main 0\nlabel {}", label))]);
+        assert!(
+            matches!(
+                translate(&files, Options::default()),
+                Err(TranslateError::Parse { line: 2, .. })
+            ),
+            "label {} was accepted",
+            label
+        );
+    }
+}
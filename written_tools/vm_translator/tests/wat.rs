@@ -0,0 +1,66 @@
+//! The WebAssembly backend: modules are validated with `wat2wasm` and their `run`
+//! function is invoked with `wasmtime`, which prints the value on top of the stack
+
+mod common;
+
+use std::{fs, process::Command};
+
+use common::{build, has_tool, load, program, run_with_output, scratch_dir};
+use vm_translator::{translate, Options, Target, VmFile};
+
+/// Result of `run`, `None` if `wat2wasm` or `wasmtime` is missing
+fn run_program(test: &str, files: &[VmFile]) -> Option<i32> {
+    if !has_tool("wat2wasm") || !has_tool("wasmtime") {
+        eprintln!("skipping {}: wat2wasm or wasmtime not found", test);
+        return None;
+    }
+    let options = Options {
+        bootstrap: true,
+        target: Target::Wat,
+    };
+    let dir = scratch_dir(test);
+    fs::write(dir.join("Prog.wat"), translate(files, options).unwrap()).unwrap();
+    build(
+        Command::new("wat2wasm")
+            .current_dir(&dir)
+            .args(["Prog.wat", "-o", "Prog.wasm"]),
+    );
+    let (status, stdout) = run_with_output(Command::new("wasmtime").current_dir(&dir).args([
+        "run",
+        "--invoke",
+        "run",
+        "Prog.wasm",
+    ]));
+    assert_eq!(status, 0);
+    Some(stdout.trim().parse().unwrap())
+}
+
+#[test]
+fn halts_in_a_label_jumping_to_itself() {
+    let files = load(&["projects/08/FunctionCalls/FibonacciElement"]);
+    if let Some(top) = run_program("fibonacci_element", &files) {
+        assert_eq!(top, 3);
+    }
+}
+
+#[test]
+fn returns_the_top_of_the_stack_when_sys_init_returns() {
+    let files = program(
+        "Sys",
+        "function Sys.init 0
+push constant 300
+push constant 7
+add
+neg
+return",
+    );
+    if let Some(top) = run_program("sys_init_returns", &files) {
+        assert_eq!(top, -307);
+    }
+}
+
+#[test]
+fn runs_a_program_with_the_os_to_its_end() {
+    let files = load(&["projects/11/Seven", "tools/OS"]);
+    run_program("seven_with_os", &files);
+}
@@ -0,0 +1,80 @@
+//! The x86-64 backend: programs are assembled with `as` and `ld` and run, the exit
+//! status is the low byte of the top of the stack when the program ends
+
+mod common;
+
+use std::{fs, process::Command};
+
+use common::{build, has_tool, load, program, run, scratch_dir};
+use vm_translator::{translate, Options, Target, VmFile};
+
+/// `None` if `as` or `ld` is missing
+fn exit_status(test: &str, files: &[VmFile]) -> Option<i32> {
+    if !has_tool("as") || !has_tool("ld") {
+        eprintln!("skipping {}: as or ld not found", test);
+        return None;
+    }
+    let options = Options {
+        bootstrap: true,
+        target: Target::X86_64,
+    };
+    let dir = scratch_dir(test);
+    fs::write(dir.join("Prog.s"), translate(files, options).unwrap()).unwrap();
+    build(
+        Command::new("as")
+            .current_dir(&dir)
+            .args(["-o", "Prog.o", "Prog.s"]),
+    );
+    build(
+        Command::new("ld")
+            .current_dir(&dir)
+            .args(["-o", "Prog", "Prog.o"]),
+    );
+    Some(run(&mut Command::new(dir.join("Prog"))))
+}
+
+#[test]
+fn halts_in_a_label_jumping_to_itself() {
+    // Sys.init pushes fibonacci(4) and loops in `label WHILE, goto WHILE`
+    let files = load(&["projects/08/FunctionCalls/FibonacciElement"]);
+    if let Some(status) = exit_status("fibonacci_element", &files) {
+        assert_eq!(status, 3);
+    }
+}
+
+#[test]
+fn exits_when_sys_init_returns() {
+    let files = program(
+        "Sys",
+        "function Sys.init 0
+push constant 300
+push constant 7
+add
+return",
+    );
+    if let Some(status) = exit_status("sys_init_returns", &files) {
+        assert_eq!(status, 307 % 256);
+    }
+}
+
+#[test]
+fn halts_on_sys_halt_without_the_os() {
+    let files = program(
+        "Sys",
+        "function Sys.init 0
+push constant 42
+call Sys.halt 0
+push constant 1
+return",
+    );
+    if let Some(status) = exit_status("sys_halt_without_os", &files) {
+        assert_eq!(status, 42);
+    }
+}
+
+#[test]
+fn runs_a_program_with_the_os_to_its_end() {
+    // the OS Sys.init calls Main.main and then Sys.halt, which loops in `while (true)`
+    let files = load(&["projects/11/Seven", "tools/OS"]);
+    exit_status("seven_with_os", &files);
+}